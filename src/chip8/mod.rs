@@ -1,4 +0,0 @@
-pub mod cpu;
-mod keyboard;
-mod display;
-mod sound;
\ No newline at end of file
@@ -0,0 +1,244 @@
+//! The configuration files of the window.
+//!
+//! Settings are read from the global file at [`global_path`] and then from `<Rom>.toml` next to
+//! the ROM, whose settings win. Both files are optional.
+//!
+//! ```toml
+//! [keys]
+//! # the layout to start from: qwerty (the default), qwertz, azerty, dvorak or colemak
+//! preset = "azerty"
+//! # hex key = a host key or a list of them, replacing the keys the preset binds it to
+//! 1 = ["Up", "W"]
+//! 4 = ["Down", "S"]
+//!
+//! [gamepad]
+//! # how far a stick or trigger has to be pushed to press its hex key, from 0 to 1 (default 0.5)
+//! threshold = 0.3
+//! # hex key = controller inputs, replacing the default ones
+//! 1 = ["dpup", "lefty-"]
+//! 4 = ["dpdown", "lefty+"]
+//!
+//! [display]
+//! # default, green, amber, lcd, high-contrast or octo
+//! palette = "amber"
+//! # replaces the palette's first colours: background, foreground and the XO-CHIP colours of
+//! # plane 2 and of both planes
+//! colors = ["#202020", "#E0E0E0"]
+//! # the initial window size in window pixels per 64x32 pixel (default 16)
+//! scale = 8
+//! # scale by whole numbers only, leaving a wider border (default false)
+//! integer_scaling = true
+//! # start in fullscreen (default false)
+//! fullscreen = true
+//! # against flicker, show pixels set in any of the last N frames, "frames:2", or let pixels
+//! # fade out, "decay:0.6", from 0 to below 1 (default "off")
+//! persistence = "frames:2"
+//! ```
+//!
+//! Host keys and controller inputs are named as in [`crate::keymap`].
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::keymap::{DEFAULT_PRESET, Keymap};
+use crate::palette::{Palette, Rgb};
+use crate::phosphor::Persistence;
+
+/// The initial window size in window pixels per pixel of the 64x32 screen.
+pub const DEFAULT_SCALE: u32 = 16;
+
+/// The part of its full range a stick or trigger has to be pushed by default.
+pub const DEFAULT_STICK_THRESHOLD: f32 = 0.5;
+
+/// The settings of one configuration file, or of several merged with [`Config::merge`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub keys: KeyConfig,
+    #[serde(default)]
+    pub gamepad: GamepadConfig,
+    #[serde(default)]
+    pub display: DisplayConfig,
+}
+
+/// The `[keys]` table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct KeyConfig {
+    pub preset: Option<String>,
+    /// Hex key names and the host keys bound to them.
+    #[serde(flatten)]
+    pub bindings: BTreeMap<String, HostKeys>,
+}
+
+/// The `[gamepad]` table.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct GamepadConfig {
+    pub threshold: Option<f32>,
+    /// Hex key names and the controller inputs bound to them.
+    #[serde(flatten)]
+    pub bindings: BTreeMap<String, HostKeys>,
+}
+
+/// The `[display]` table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DisplayConfig {
+    pub palette: Option<String>,
+    pub colors: Option<Vec<String>>,
+    pub scale: Option<u32>,
+    pub integer_scaling: Option<bool>,
+    pub fullscreen: Option<bool>,
+    pub persistence: Option<String>,
+}
+
+/// One input or several.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum HostKeys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl HostKeys {
+    pub fn names(&self) -> &[String] {
+        match self {
+            HostKeys::One(name) => std::slice::from_ref(name),
+            HostKeys::Many(names) => names,
+        }
+    }
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Config, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// Reads the file at `path`, or returns the empty configuration if there is none.
+    pub fn load(path: &Path) -> Result<Config, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// Overrides these settings with those set in `other`.
+    pub fn merge(&mut self, other: Config) {
+        if other.keys.preset.is_some() {
+            self.keys.preset = other.keys.preset;
+        }
+        self.keys.bindings.extend(other.keys.bindings);
+        if other.gamepad.threshold.is_some() {
+            self.gamepad.threshold = other.gamepad.threshold;
+        }
+        self.gamepad.bindings.extend(other.gamepad.bindings);
+        if other.display.palette.is_some() {
+            self.display.palette = other.display.palette;
+        }
+        if other.display.colors.is_some() {
+            self.display.colors = other.display.colors;
+        }
+        if other.display.scale.is_some() {
+            self.display.scale = other.display.scale;
+        }
+        if other.display.integer_scaling.is_some() {
+            self.display.integer_scaling = other.display.integer_scaling;
+        }
+        if other.display.fullscreen.is_some() {
+            self.display.fullscreen = other.display.fullscreen;
+        }
+        if other.display.persistence.is_some() {
+            self.display.persistence = other.display.persistence;
+        }
+    }
+
+    /// The preset with the configured bindings applied.
+    pub fn keymap(&self) -> Result<Keymap, String> {
+        let preset = self.keys.preset.as_deref().unwrap_or(DEFAULT_PRESET);
+        let mut keymap = Keymap::preset(preset).ok_or_else(|| {
+            let presets: Vec<&str> = Keymap::presets().collect();
+            format!("Unknown key preset: {}, expected one of {}", preset, presets.join(", "))
+        })?;
+        bind(&mut keymap, &self.keys.bindings)?;
+        Ok(keymap)
+    }
+
+    /// The default controller bindings with the configured ones applied.
+    pub fn gamepad_keymap(&self) -> Result<Keymap, String> {
+        let mut keymap = Keymap::gamepad();
+        bind(&mut keymap, &self.gamepad.bindings)?;
+        Ok(keymap)
+    }
+
+    /// The named palette with the configured colours applied.
+    pub fn palette(&self) -> Result<Palette, String> {
+        let mut palette = match self.display.palette {
+            Some(ref name) => Palette::named(name).ok_or_else(|| {
+                let names: Vec<&str> = Palette::names().collect();
+                format!("Unknown palette: {}, expected one of {}", name, names.join(", "))
+            })?,
+            None => Palette::default(),
+        };
+        if let Some(ref colors) = self.display.colors {
+            let colors = colors.iter().map(|color| color.parse()).collect::<Result<Vec<Rgb>, _>>()?;
+            palette.set_colors(&colors)?;
+        }
+        Ok(palette)
+    }
+
+    /// The initial window size in window pixels per pixel of the 64x32 screen.
+    pub fn scale(&self) -> Result<u32, String> {
+        match self.display.scale.unwrap_or(DEFAULT_SCALE) {
+            0 => Err("Invalid scale: 0, expected 1 or more".to_string()),
+            scale => Ok(scale),
+        }
+    }
+
+    /// How long pixels stay visible after they go off.
+    pub fn persistence(&self) -> Result<Persistence, String> {
+        match self.display.persistence {
+            Some(ref persistence) => persistence.parse(),
+            None => Ok(Persistence::Off),
+        }
+    }
+
+    /// The part of its full range a stick or trigger has to be pushed to press its hex key.
+    pub fn stick_threshold(&self) -> Result<f32, String> {
+        match self.gamepad.threshold.unwrap_or(DEFAULT_STICK_THRESHOLD) {
+            threshold if threshold > 0.0 && threshold <= 1.0 => Ok(threshold),
+            threshold => Err(format!("Invalid gamepad threshold: {}, expected more than 0 and at most 1", threshold)),
+        }
+    }
+}
+
+fn bind(keymap: &mut Keymap, bindings: &BTreeMap<String, HostKeys>) -> Result<(), String> {
+    for (hex, keys) in bindings.iter() {
+        let hex = u8::from_str_radix(hex, 16).ok()
+            .filter(|_| hex.len() == 1)
+            .ok_or_else(|| format!("Not a hex key: {}, expected 0 to F", hex))?;
+        keymap.bind(hex, keys.names());
+    }
+    Ok(())
+}
+
+/// `chip8/config.toml` in the user's configuration directory: `$XDG_CONFIG_HOME`, `~/.config` or
+/// `%APPDATA%`.
+pub fn global_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .map(|dir| dir.join("chip8").join("config.toml"))
+}
+
+/// The configuration file of the ROM at `rom`.
+pub fn rom_path(rom: &str) -> PathBuf {
+    PathBuf::from(format!("{}.toml", rom))
+}
@@ -0,0 +1,1044 @@
+use crate::error::Chip8Error;
+use crate::framebuffer::{FrameBuffer, HIRES_HEIGHT, HIRES_WIDTH, LORES_HEIGHT, LORES_WIDTH};
+use crate::opcode::Opcode;
+use crate::quirks::{Platform, Quirks};
+use crate::random::{RandomSource, Xorshift};
+use crate::state::{StateReader, StateWriter};
+use crate::timing;
+
+const MEMORY_FONT_START: u16 = 0x0000;
+const MEMORY_BIG_FONT_START: u16 = MEMORY_FONT_START + SPRITES.len() as u16;
+
+/// Address programs are loaded at and start executing from.
+pub const MEMORY_PROGRAM_BASE: u16 = 0x0200;
+
+const MEMORY_LEN: usize = 0x1000;
+
+/// Instructions run per 60 Hz frame unless configured otherwise.
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: u32 = 16;
+
+const SPRITES: [u8; 0x50] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+// SUPER-CHIP 8x10 digits, extended with A-F as in XO-CHIP
+const BIG_SPRITES: [u8; 0xA0] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+const RPL_FLAGS: usize = 8;
+
+/// The deepest call stack of any platform. The COSMAC VIP had room for 12 return addresses,
+/// CHIP-48, SUPER-CHIP and XO-CHIP for 16.
+pub const MAX_STACK_DEPTH: usize = 16;
+
+// XO-CHIP audio pattern playback rate at pitch 64, in bits per second
+pub const AUDIO_PATTERN_BASE_RATE: f32 = 4000.0;
+const DEFAULT_PITCH: u8 = 64;
+
+/// An FX0A instruction waiting for a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct KeyWait {
+    /// The register the key is stored in.
+    register: usize,
+    /// The key pressed since the wait began, if it has to be released before the wait ends.
+    key: Option<u8>,
+}
+
+/// The interpreter core: registers, memory, timers, framebuffer and key state.
+///
+/// `Chip8` knows nothing about windows, audio devices or host keyboards. A frontend
+/// feeds it key state with [`Chip8::set_key`], drives it with [`Chip8::run_frame`]
+/// at 60 Hz and reads back [`Chip8::frame_buffer`] and [`Chip8::sound_active`].
+pub struct Chip8 {
+    registers: [u8; 16],
+    pc: u16,
+    reg_index: u16,
+    stack: [u16; MAX_STACK_DEPTH],
+    // the number of return addresses on the stack
+    sp: usize,
+    stack_depth: usize,
+    delay_timer: u8,
+    sound_timer: u8,
+    frame_buffer: FrameBuffer,
+    hires: bool,
+    halted: bool,
+    rpl_flags: [u8; RPL_FLAGS],
+    planes: u8,
+    audio_pattern: Option<[u8; 16]>,
+    pitch: u8,
+    memory: Vec<u8>,
+    key_wait: Option<KeyWait>,
+    keys: [bool; 16],
+    last_pressed_key: Option<u8>,
+    quirks: Quirks,
+    vblank_wait: bool,
+    seed: u64,
+    random: Box<dyn RandomSource>,
+    instructions_per_frame: u32,
+    vip_timing: bool,
+    // cycles of the current frame already used, by an instruction that ran past the last frame
+    frame_cycles: u32,
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chip8 {
+    pub fn new() -> Self {
+        Self::with_quirks(Quirks::default())
+    }
+
+    pub fn with_quirks(quirks: Quirks) -> Self {
+        let seed = rand::random();
+        Chip8 {
+            registers: [0; 16],
+            pc: 0,
+            reg_index: 0,
+            stack: [0; MAX_STACK_DEPTH],
+            sp: 0,
+            stack_depth: MAX_STACK_DEPTH,
+            delay_timer: 0u8,
+            sound_timer: 0u8,
+            frame_buffer: FrameBuffer::new(LORES_WIDTH, LORES_HEIGHT),
+            hires: false,
+            halted: false,
+            rpl_flags: [0; RPL_FLAGS],
+            planes: 0x01,
+            audio_pattern: None,
+            pitch: DEFAULT_PITCH,
+            memory: vec![0; MEMORY_LEN],
+            key_wait: None,
+            keys: [false; 16],
+            last_pressed_key: None,
+            quirks,
+            vblank_wait: false,
+            seed,
+            random: Box::new(Xorshift::new(seed)),
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            vip_timing: false,
+            frame_cycles: 0,
+        }
+    }
+
+    /// Creates a core with the quirks, address space and call stack depth of `platform`.
+    pub fn with_platform(platform: Platform) -> Self {
+        let mut chip8 = Self::with_quirks(platform.quirks());
+        chip8.memory = vec![0; platform.memory_len()];
+        chip8.stack_depth = platform.stack_depth();
+        chip8
+    }
+
+    /// Loads the fonts and `rom` into memory and points PC at the start of the program.
+    pub fn init(&mut self, rom: &[u8]) -> Result<(), Chip8Error> {
+        let base = MEMORY_PROGRAM_BASE as usize;
+        let max = self.memory.len() - base;
+        if rom.len() > max {
+            return Err(Chip8Error::RomTooLarge { size: rom.len(), max });
+        }
+        self.pc = MEMORY_PROGRAM_BASE;
+        let font = MEMORY_FONT_START as usize;
+        self.memory[font..font + SPRITES.len()].copy_from_slice(&SPRITES);
+        let big_font = MEMORY_BIG_FONT_START as usize;
+        self.memory[big_font..big_font + BIG_SPRITES.len()].copy_from_slice(&BIG_SPRITES);
+        self.memory[base..base + rom.len()].copy_from_slice(rom);
+        Ok(())
+    }
+
+    /// Serializes the complete machine state in the format described in [`crate::state`].
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        writer.chunk(b"CPU ", |c| {
+            c.bytes(&self.registers);
+            c.u16(self.pc);
+            c.u16(self.reg_index);
+            c.u8(self.delay_timer);
+            c.u8(self.sound_timer);
+            c.bool(self.hires);
+            c.bool(self.halted);
+            c.u8(self.planes);
+            c.u8(self.pitch);
+            c.bytes(&self.rpl_flags);
+        });
+        writer.chunk(b"STAK", |c| {
+            c.u16(self.sp as u16);
+            self.stack().iter().for_each(|&address| c.u16(address));
+        });
+        writer.chunk(b"MEM ", |c| c.bytes(&self.memory));
+        writer.chunk(b"SCRN", |c| {
+            c.u16(self.frame_buffer.width() as u16);
+            c.u16(self.frame_buffer.height() as u16);
+            c.bytes(self.frame_buffer.pixels());
+        });
+        writer.chunk(b"KEYS", |c| {
+            self.keys.iter().for_each(|&pressed| c.bool(pressed));
+            c.u8(self.last_pressed_key.unwrap_or(0xFF));
+        });
+        writer.chunk(b"WAIT", |c| {
+            c.u8(self.key_wait.map_or(0xFF, |wait| wait.register as u8));
+            c.u8(self.key_wait.and_then(|wait| wait.key).unwrap_or(0xFF));
+        });
+        if let Some(ref pattern) = self.audio_pattern {
+            writer.chunk(b"AUDP", |c| c.bytes(pattern));
+        }
+        writer.chunk(b"TIME", |c| c.u32(self.frame_cycles));
+        writer.chunk(b"RNG ", |c| {
+            let name = self.random.name();
+            c.u8(name.len() as u8);
+            c.bytes(name.as_bytes());
+            c.bytes(&self.random.state());
+        });
+        writer.finish()
+    }
+
+    /// Restores a state written by [`Chip8::save_state`]. The core is left unchanged if the
+    /// state is invalid. Quirks are not part of the state and keep their current setting.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), Chip8Error> {
+        let invalid = |reason: &str| Chip8Error::InvalidState { reason: reason.to_string() };
+        let reader = StateReader::parse(state)?;
+
+        let mut cpu = reader.required(b"CPU ")?;
+        let mut registers = [0; 16];
+        registers.copy_from_slice(cpu.bytes(16)?);
+        let pc = cpu.u16()?;
+        let reg_index = cpu.u16()?;
+        let delay_timer = cpu.u8()?;
+        let sound_timer = cpu.u8()?;
+        let hires = cpu.bool()?;
+        let halted = cpu.bool()?;
+        let planes = cpu.u8()?;
+        let pitch = cpu.u8()?;
+        let mut rpl_flags = [0; RPL_FLAGS];
+        rpl_flags.copy_from_slice(cpu.bytes(RPL_FLAGS)?);
+
+        let mut stack_chunk = reader.required(b"STAK")?;
+        let depth = stack_chunk.u16()?;
+        let mut stack = (0..depth).map(|_| stack_chunk.u16()).collect::<Result<Vec<u16>, _>>()?;
+        if reader.version() < 3 && stack.len() >= 64 {
+            // versions 1 and 2 stored the 64 unused entries the stack used to start with
+            stack.drain(..64);
+        }
+
+        let memory = reader.required(b"MEM ")?.rest().to_vec();
+        if memory.len() <= MEMORY_PROGRAM_BASE as usize {
+            return Err(invalid("memory too small"));
+        }
+
+        let mut screen = reader.required(b"SCRN")?;
+        let (width, height) = (screen.u16()? as usize, screen.u16()? as usize);
+        let expected = if hires { (HIRES_WIDTH, HIRES_HEIGHT) } else { (LORES_WIDTH, LORES_HEIGHT) };
+        if (width, height) != expected {
+            return Err(invalid("screen size does not match the resolution"));
+        }
+        let frame_buffer = FrameBuffer::from_pixels(width, height, screen.rest().to_vec())
+            .ok_or_else(|| invalid("screen size does not match the pixels"))?;
+
+        let mut keys = [false; 16];
+        let mut last_pressed_key = None;
+        if let Some(mut chunk) = reader.chunk(b"KEYS") {
+            for key in keys.iter_mut() {
+                *key = chunk.bool()?;
+            }
+            last_pressed_key = Some(chunk.u8()?).filter(|&key| key < 16);
+        }
+        let mut key_wait = None;
+        if let Some(mut chunk) = reader.chunk(b"WAIT") {
+            if reader.version() < 2 {
+                // version 1 kept the FX0A opcode on top of the call stack while waiting
+                let (paused, events) = (chunk.bool()?, chunk.u16()?);
+                if paused && events & 0x0001 != 0 {
+                    let opcode = stack.pop().map(Opcode).ok_or_else(|| invalid("FX0A missing from the stack"))?;
+                    key_wait = Some(KeyWait { register: opcode.x(), key: None });
+                }
+            } else {
+                let register = chunk.u8()?;
+                let key = chunk.u8()?;
+                if register < 16 {
+                    key_wait = Some(KeyWait { register: register as usize, key: Some(key).filter(|&key| key < 16) });
+                }
+            }
+        }
+        let frame_cycles = match reader.chunk(b"TIME") {
+            Some(mut chunk) => chunk.u32()?,
+            None => 0,
+        };
+        let audio_pattern = match reader.chunk(b"AUDP") {
+            Some(mut chunk) => {
+                let mut pattern = [0; 16];
+                pattern.copy_from_slice(chunk.bytes(16)?);
+                Some(pattern)
+            }
+            None => None,
+        };
+
+        if stack.len() > self.stack_depth {
+            return Err(invalid("call stack deeper than the configured stack depth"));
+        }
+
+        // the random source is configuration like the quirks, only its state is restored
+        if let Some(mut chunk) = reader.chunk(b"RNG ") {
+            let len = chunk.u8()? as usize;
+            let name = chunk.bytes(len)?;
+            if name == self.random.name().as_bytes() && !self.random.set_state(chunk.rest()) {
+                return Err(invalid("invalid random number source state"));
+            }
+        }
+
+        self.registers = registers;
+        self.pc = pc;
+        self.reg_index = reg_index;
+        self.stack[..stack.len()].copy_from_slice(&stack);
+        self.sp = stack.len();
+        self.delay_timer = delay_timer;
+        self.sound_timer = sound_timer;
+        self.frame_buffer = frame_buffer;
+        self.hires = hires;
+        self.halted = halted;
+        self.rpl_flags = rpl_flags;
+        self.planes = planes;
+        self.audio_pattern = audio_pattern;
+        self.pitch = pitch;
+        self.memory = memory;
+        self.key_wait = key_wait;
+        self.keys = keys;
+        self.last_pressed_key = last_pressed_key;
+        self.vblank_wait = false;
+        self.frame_cycles = frame_cycles;
+        Ok(())
+    }
+
+    /// The number of instructions [`Chip8::run_frame`] runs before ticking the timers.
+    pub fn instructions_per_frame(&self) -> u32 {
+        self.instructions_per_frame
+    }
+
+    /// Sets the CPU speed. The timers tick once per frame whatever the speed, so at 60 frames per
+    /// second `instructions` per frame is a clock of `60 * instructions` instructions per second.
+    pub fn set_instructions_per_frame(&mut self, instructions: u32) {
+        self.instructions_per_frame = instructions.max(1);
+    }
+
+    /// Whether frames are timed by the COSMAC VIP model in [`crate::timing`].
+    pub fn vip_timing(&self) -> bool {
+        self.vip_timing
+    }
+
+    /// Times frames by the cycle costs of the COSMAC VIP instead of a fixed number of instructions
+    /// per frame. DXYN then always waits for the next frame, as on the VIP.
+    pub fn set_vip_timing(&mut self, enabled: bool) {
+        self.vip_timing = enabled;
+        self.frame_cycles = 0;
+    }
+
+    /// Runs one 60 Hz frame: a batch of instructions followed by a timer tick.
+    pub fn run_frame(&mut self) -> Result<(), Chip8Error> {
+        self.run_frame_with(|_| {})
+    }
+
+    /// Runs one frame like [`Chip8::run_frame`], calling `before_step` before every instruction.
+    pub fn run_frame_with<F: FnMut(&mut Chip8)>(&mut self, mut before_step: F) -> Result<(), Chip8Error> {
+        self.vblank_wait = false;
+        if self.vip_timing {
+            return self.run_vip_frame(before_step);
+        }
+        for _ in 0..self.instructions_per_frame {
+            before_step(self);
+            self.step()?;
+            if self.key_wait.is_some() || self.vblank_wait {
+                break;
+            }
+        }
+        self.tick_timers();
+        Ok(())
+    }
+
+    /// Runs instructions until their cycle costs fill the frame. An instruction that runs past the
+    /// end of the frame takes its remaining cycles from the next one.
+    fn run_vip_frame<F: FnMut(&mut Chip8)>(&mut self, mut before_step: F) -> Result<(), Chip8Error> {
+        while self.frame_cycles < timing::FRAME_CYCLES {
+            before_step(self);
+            let (pc, registers, index, waiting) = (self.pc, self.registers, self.reg_index, self.key_wait.is_some());
+            let opcode = self.read_word(pc).map(Opcode).unwrap_or(Opcode(0));
+            let draw = (opcode.0 & 0xF000 == 0xD000).then(|| self.vip_draw_cycles(opcode));
+            self.step()?;
+            if self.key_wait.is_some() || self.halted {
+                self.frame_cycles = 0;
+                break;
+            }
+            if waiting {
+                // the poll that ended an FX0A wait ran no instruction
+                continue;
+            }
+            let cycles = timing::cycles(opcode, &registers, index, self.pc != pc.wrapping_add(2));
+            if let Some(draw) = draw {
+                // the VIP waits for the display interrupt and then draws in the frame after it
+                let interrupt = if self.frame_cycles + cycles > timing::FRAME_CYCLES { 2 } else { 1 };
+                self.frame_cycles = interrupt * timing::FRAME_CYCLES + draw;
+                break;
+            }
+            self.frame_cycles += cycles;
+        }
+        self.frame_cycles = self.frame_cycles.saturating_sub(timing::FRAME_CYCLES);
+        self.tick_timers();
+        Ok(())
+    }
+
+    /// The cycles the VIP spends drawing the sprite of the DXYN `opcode` onto the current screen.
+    fn vip_draw_cycles(&self, opcode: Opcode) -> u32 {
+        let start = (self.reg_index as usize).min(self.memory.len());
+        let end = (start + opcode.n() as usize).min(self.memory.len());
+        let (vx, vy) = (self.registers[opcode.x()], self.registers[opcode.y()]);
+        timing::draw_cycles(vx, vy, &self.memory[start..end], &self.frame_buffer)
+    }
+
+    /// Executes a single instruction, or polls for a key while FX0A is waiting.
+    pub fn step(&mut self) -> Result<(), Chip8Error> {
+        if self.halted {
+            return Ok(());
+        }
+        if let Some(wait) = self.key_wait {
+            self.poll_key_wait(wait);
+            return Ok(());
+        }
+        let pc = self.pc;
+        let opcode = self.read_opcode().map_err(|fault| fault.at(pc, 0))?;
+        self.pc = self.pc.wrapping_add(0x0002);// point to next instruction
+        self.exec_opcode(&opcode).map_err(|fault| fault.at(pc, opcode.0))
+    }
+
+    /// Decrements the delay and sound timers, to be called at 60 Hz.
+    pub fn tick_timers(&mut self) {
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
+        self.random.frame();
+    }
+
+    /// The seed of the random number source used by CXNN, random unless set.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restarts the random number source used by CXNN from `seed`, making runs reproducible.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.random.reseed(seed);
+    }
+
+    /// The name of the random number source used by CXNN, see [`RandomSource::name`].
+    pub fn random_source_name(&self) -> &'static str {
+        self.random.name()
+    }
+
+    /// Replaces the random number source used by CXNN, seeding it with the current seed.
+    pub fn set_random_source(&mut self, mut random: Box<dyn RandomSource>) {
+        random.reseed(self.seed);
+        self.random = random;
+    }
+
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.registers
+    }
+
+    pub fn set_register(&mut self, x: usize, value: u8) {
+        self.registers[x] = value;
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+    }
+
+    /// The index register I.
+    pub fn index(&self) -> u16 {
+        self.reg_index
+    }
+
+    pub fn set_index(&mut self, index: u16) {
+        self.reg_index = index;
+    }
+
+    /// The return addresses on the call stack, the most recent call last.
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.sp]
+    }
+
+    /// The number of return addresses the call stack has room for.
+    pub fn stack_depth(&self) -> usize {
+        self.stack_depth
+    }
+
+    /// Sets the room on the call stack, from 1 to [`MAX_STACK_DEPTH`] return addresses. A call
+    /// beyond it fails with [`Chip8Error::StackOverflow`]. Addresses already beyond it are dropped.
+    pub fn set_stack_depth(&mut self, depth: usize) {
+        self.stack_depth = depth.clamp(1, MAX_STACK_DEPTH);
+        self.sp = self.sp.min(self.stack_depth);
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.delay_timer = value;
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.sound_timer = value;
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        &mut self.memory
+    }
+
+    /// Whether FX0A is waiting for a key.
+    pub fn waiting_for_key(&self) -> bool {
+        self.key_wait.is_some()
+    }
+
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn sound_active(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    pub fn frame_buffer(&self) -> &FrameBuffer {
+        &self.frame_buffer
+    }
+
+    /// The XO-CHIP audio pattern loaded by F002, 128 one-bit samples played back while the sound
+    /// timer is active. `None` until the program loads one, in which case a plain beep is played.
+    pub fn audio_pattern(&self) -> Option<&[u8; 16]> {
+        self.audio_pattern.as_ref()
+    }
+
+    /// The playback rate of the audio pattern in bits per second, set by FX3A.
+    pub fn audio_pattern_rate(&self) -> f32 {
+        AUDIO_PATTERN_BASE_RATE * 2f32.powf((self.pitch as f32 - 64.0) / 48.0)
+    }
+
+    /// Whether the program has exited with the SUPER-CHIP 00FD instruction.
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Updates the state of hex key `key` (0x0..=0xF).
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        let key = key & 0x0F;
+        if pressed && !self.keys[key as usize] {
+            self.last_pressed_key = Some(key);
+        }
+        self.keys[key as usize] = pressed;
+    }
+
+    pub fn is_key_pressed(&self, key: u8) -> bool {
+        self.keys[(key & 0x0F) as usize]
+    }
+
+    /// Ends the FX0A wait once a key is pressed, or once it is released again with the
+    /// `key_wait_release` quirk. A key held down when the wait began counts as pressed.
+    fn poll_key_wait(&mut self, wait: KeyWait) {
+        let key = match wait.key {
+            Some(key) => key,
+            None => match self.last_pressed_key.take().or_else(|| (0..16).find(|&key| self.keys[key as usize])) {
+                Some(key) => key,
+                None => return,
+            },
+        };
+        if self.quirks.key_wait_release && self.keys[key as usize] {
+            self.key_wait = Some(KeyWait { key: Some(key), ..wait });
+        } else {
+            self.registers[wait.register] = key;
+            self.key_wait = None;
+        }
+    }
+
+    #[inline]
+    fn read_opcode(&mut self) -> Result<Opcode, Fault> {
+        self.read_word(self.pc).map(Opcode)
+    }
+
+    fn read_word(&self, address: u16) -> Result<u16, Fault> {
+        // 大端序
+        let high = self.load(address as usize)? as u16;
+        let low = self.load(address as usize + 1)? as u16;
+        Ok((high) << 8 | low)
+    }
+
+    fn load(&self, address: usize) -> Result<u8, Fault> {
+        self.memory.get(address).copied().ok_or(Fault::MemoryOutOfBounds(address))
+    }
+
+    fn store(&mut self, address: usize, value: u8) -> Result<(), Fault> {
+        let byte = self.memory.get_mut(address).ok_or(Fault::MemoryOutOfBounds(address))?;
+        *byte = value;
+        Ok(())
+    }
+
+    /// Skips the following instruction, which is 4 bytes long if it is the XO-CHIP F000 NNNN.
+    fn skip_next(&mut self) {
+        if let Ok(0xF000) = self.read_word(self.pc) {
+            self.pc = self.pc.wrapping_add(0x0004);
+        } else {
+            self.pc = self.pc.wrapping_add(0x0002);
+        }
+    }
+
+    fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        if hires {
+            self.frame_buffer.resize(HIRES_WIDTH, HIRES_HEIGHT);
+        } else {
+            self.frame_buffer.resize(LORES_WIDTH, LORES_HEIGHT);
+        }
+    }
+
+    /// Draws the sprite at I to (vx, vy). N rows of 8 pixels, or 16x16 pixels if N is 0.
+    /// With both XO-CHIP planes selected, the data for plane 2 follows the data for plane 1.
+    /// Sets VF to 1 if any set pixels are changed to unset, and 0 otherwise.
+    fn draw_sprite(&mut self, vx: u8, vy: u8, n: u8) -> Result<(), Fault> {
+        let width = self.frame_buffer.width();
+        let height = self.frame_buffer.height();
+        let (cols, rows) = if n == 0 { (16, 16) } else { (8, n as usize) };
+        let bytes_per_row = cols / 8;
+        // the starting position always wraps, only pixels past the edge are clipped
+        let origin_x = vx as usize % width;
+        let origin_y = vy as usize % height;
+        self.registers[0xF] = 0;
+        let mut address = self.reg_index as usize;
+        for plane in [0x01, 0x02] {
+            if self.planes & plane == 0 {
+                continue;
+            }
+            for row in 0..rows {
+                let mut sprite = (self.load(address)? as u16) << 8;
+                if bytes_per_row == 2 {
+                    sprite |= self.load(address + 1)? as u16;
+                }
+                address += bytes_per_row;
+                for col in 0..cols {
+                    let px = origin_x + col;
+                    let py = origin_y + row;
+                    // msb of the row is set the plot pixel
+                    if sprite & 0x8000 > 0
+                        && !(self.quirks.clip_sprites && (px >= width || py >= height))
+                        && self.frame_buffer.flip(px, py, plane) {
+                        self.registers[0xF] = 1
+                    }
+                    // left shift by one
+                    sprite <<= 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The registers from VX to VY inclusive, in descending order if X > Y.
+    fn register_range(x: usize, y: usize) -> Box<dyn Iterator<Item=usize>> {
+        if x <= y {
+            Box::new(x..=y)
+        } else {
+            Box::new((y..=x).rev())
+        }
+    }
+
+    /// The value shifted by 8XY6/8XYE.
+    fn shift_operand(&self, x: usize, y: usize) -> u8 {
+        if self.quirks.shift_uses_vy {
+            self.registers[y]
+        } else {
+            self.registers[x]
+        }
+    }
+
+    fn reset_vf(&mut self) {
+        if self.quirks.vf_reset {
+            self.registers[0xF] = 0;
+        }
+    }
+
+    fn exec_opcode(&mut self, opcode: &Opcode) -> Result<(), Fault> {
+        let x = opcode.x();
+        let y = opcode.y();
+        let n = opcode.n();
+        let nn = opcode.nn();
+        let nnn = opcode.nnn();
+        let opcode = opcode.0;
+        #[cfg(feature = "log_debug")]
+        println!("{:04X} {}", opcode, crate::disasm::mnemonic(opcode).unwrap_or_default());
+        match opcode & 0xF000 {
+            0x0000 => {
+                match opcode {
+                    0x00C0..=0x00CF => {
+                        // 00CN: Scroll the screen down by N pixels (SUPER-CHIP)
+                        self.frame_buffer.scroll_down(n as usize, self.planes);
+                    }
+                    0x00D0..=0x00DF => {
+                        // 00DN: Scroll the screen up by N pixels (XO-CHIP)
+                        self.frame_buffer.scroll_up(n as usize, self.planes);
+                    }
+                    0x00E0 => {
+                        // 00E0: Clear the screen
+                        self.frame_buffer.clear(self.planes);
+                    }
+                    0x00EE => {
+                        // 00EE: Return from subroutine
+                        if self.sp == 0 {
+                            return Err(Fault::StackUnderflow);
+                        }
+                        self.sp -= 1;
+                        self.pc = self.stack[self.sp];
+                    }
+                    0x00FB => {
+                        // 00FB: Scroll the screen right by 4 pixels (SUPER-CHIP)
+                        self.frame_buffer.scroll_right(4, self.planes);
+                    }
+                    0x00FC => {
+                        // 00FC: Scroll the screen left by 4 pixels (SUPER-CHIP)
+                        self.frame_buffer.scroll_left(4, self.planes);
+                    }
+                    0x00FD => {
+                        // 00FD: Exit the interpreter (SUPER-CHIP)
+                        self.halted = true;
+                    }
+                    0x00FE => {
+                        // 00FE: Switch to 64x32 low resolution mode (SUPER-CHIP)
+                        self.set_hires(false);
+                    }
+                    0x00FF => {
+                        // 00FF: Switch to 128x64 high resolution mode (SUPER-CHIP)
+                        self.set_hires(true);
+                    }
+                    // 0NNN: Execute RCA 1802 machine language routine at address NNN
+                    _ => return Err(Fault::UnknownOpcode)
+                }
+            }
+            0x1000 => {
+                // 1NNN: Jump to address NNN
+                self.pc = nnn;
+            }
+            0x2000 => {
+                // 2NNN: Call subroutine at address NNN
+                if self.sp >= self.stack_depth {
+                    return Err(Fault::StackOverflow);
+                }
+                self.stack[self.sp] = self.pc;
+                self.sp += 1;
+                self.pc = nnn;
+            }
+            0x3000 => {
+                // 3XNN: Skip the following instruction if the value of register VX equals NN
+                if self.registers[x] == nn {
+                    self.skip_next();
+                };
+            }
+            0x4000 => {
+                // 4XNN: Skip the following instruction if the value of register VX is not equal to NN
+                if self.registers[x] != nn {
+                    self.skip_next();
+                }
+            }
+            0x5000 => {
+                match opcode & 0x000F {
+                    0x0000 => {
+                        // 5XY0: Skip the following instruction if the value of register VX is equal to the value of register VY
+                        if self.registers[x] == self.registers[y] {
+                            self.skip_next();
+                        }
+                    }
+                    0x0002 => {
+                        // 5XY2: Store VX to VY inclusive in memory starting at address I, I is not changed (XO-CHIP)
+                        for (offset, i) in Self::register_range(x, y).enumerate() {
+                            self.store(self.reg_index as usize + offset, self.registers[i])?;
+                        }
+                    }
+                    0x0003 => {
+                        // 5XY3: Fill VX to VY inclusive from memory starting at address I, I is not changed (XO-CHIP)
+                        for (offset, i) in Self::register_range(x, y).enumerate() {
+                            self.registers[i] = self.load(self.reg_index as usize + offset)?;
+                        }
+                    }
+                    _ => return Err(Fault::UnknownOpcode)
+                }
+            }
+            0x6000 => {
+                // 6XNN: Set VX to NN
+                self.registers[x] = nn;
+            }
+            0x7000 => {
+                // 7XNN: Add NN to VX
+                let (val, _) = self.registers[x].overflowing_add(nn);
+                self.registers[x] = val;
+            }
+            0x8000 => {
+                match opcode & 0x000F {
+                    0x0000 => {
+                        // 8XY0: Set VX to the value in VY
+                        self.registers[x] = self.registers[y];
+                    }
+                    0x0001 => {
+                        // 8XY1: Set VX to VX OR VY
+                        self.registers[x] |= self.registers[y];
+                        self.reset_vf();
+                    }
+                    0x0002 => {
+                        // 8XY2: Set VX to VX AND VY
+                        self.registers[x] &= self.registers[y];
+                        self.reset_vf();
+                    }
+                    0x0003 => {
+                        // 8XY3: Set VX to VX XOR VY
+                        self.registers[x] ^= self.registers[y];
+                        self.reset_vf();
+                    }
+                    0x0004 => {
+                        // 8XY4: Add the value of register VY to register VX. Set VF to 01 if a carry occurs. Set VF to 00 if a carry does not occur
+                        let (val, is_ovf) = self.registers[x].overflowing_add(self.registers[y]);
+                        self.registers[x] = val;
+                        self.registers[0x0F] = if is_ovf { 1 } else { 0 };
+                    }
+                    0x0005 => {
+                        // 8XY5: Subtract the value of register VY from register VX. Set VF to 00 if a borrow occurs. Set VF to 01 if a borrow does not occur
+                        let (val, is_ovf) = self.registers[x].overflowing_sub(self.registers[y]);
+                        self.registers[x] = val;
+                        self.registers[0x0F] = if is_ovf { 0 } else { 1 };
+                    }
+                    0x0006 => {
+                        // 8XY6: Store the value of register VY shifted right one bit in register VX. Set register VF to the least significant bit prior to the shift
+                        // VF is written last, so the flag wins if X is F
+                        let val = self.shift_operand(x, y);
+                        self.registers[x] = val >> 0x01;
+                        self.registers[0x0F] = val & 0x01;
+                    }
+                    0x0007 => {
+                        // 8XY7: Set register VX to the value of VY minus VX. Set VF to 00 if a borrow occurs. Set VF to 01 if a borrow does not occur
+                        let (val, is_ovf) = self.registers[y].overflowing_sub(self.registers[x]);
+                        self.registers[x] = val;
+                        self.registers[0x0F] = if is_ovf { 0 } else { 1 };
+                    }
+                    0x000E => {
+                        // 8XYE: Store the value of register VY shifted left one bit in register VX. Set register VF to the most significant bit prior to the shift
+                        let val = self.shift_operand(x, y);
+                        self.registers[x] = val << 0x01;
+                        self.registers[0x0F] = val >> 0x07;
+                    }
+                    _ => return Err(Fault::UnknownOpcode)
+                }
+            }
+            0x9000 => {
+                // 9XY0: Skip the following instruction if the value of register VX is not equal to the value of register VY
+                if self.registers[x] != self.registers[y] {
+                    self.skip_next();
+                }
+            }
+            0xA000 => {
+                // ANNN: Store memory address NNN in register I
+                self.reg_index = nnn;
+            }
+            0xB000 => {
+                // Jump to location nnn + V0. The program counter is set to nnn plus the value of V0.
+                // With the jump_uses_vx quirk (BXNN), jump to XNN plus the value of VX instead.
+                let offset = if self.quirks.jump_uses_vx { self.registers[x] } else { self.registers[0] };
+                self.pc = (offset as u16) + nnn;
+            }
+            0xC000 => {
+                // CXNN	Set VX to a random number with a mask of NN
+                self.registers[x] = self.random.next_byte() & nn;
+            }
+            0xD000 => {
+                // DXYN: Draw a sprite at position VX, VY with N bytes of sprite data starting at the address stored in I
+                // Set VF to 01 if any set pixels are changed to unset, and 00 otherwise
+                // DXY0: Draw a 16x16 sprite with 32 bytes of sprite data (SUPER-CHIP)
+                self.draw_sprite(self.registers[x], self.registers[y], n)?;
+                if self.quirks.display_wait {
+                    self.vblank_wait = true;
+                }
+            }
+            0xE000 => {
+                match opcode & 0x00FF {
+                    0x009E => {
+                        // EX9E: Skip the following instruction if the key corresponding to the hex value currently stored in register VX is pressed
+                        if self.is_key_pressed(self.registers[x]) {
+                            self.skip_next();
+                        }
+                    }
+                    0x00A1 => {
+                        // EXA1: Skip the following instruction if the key corresponding to the hex value currently stored in register VX is not pressed
+                        if !self.is_key_pressed(self.registers[x]) {
+                            self.skip_next();
+                        }
+                    }
+                    _ => return Err(Fault::UnknownOpcode)
+                }
+            }
+            0xF000 => {
+                match opcode & 0x00FF {
+                    0x0000 if opcode == 0xF000 => {
+                        // F000 NNNN: Store the following 16-bit word in register I and skip it (XO-CHIP)
+                        self.reg_index = self.read_word(self.pc)?;
+                        self.pc = self.pc.wrapping_add(0x0002);
+                    }
+                    0x0001 => {
+                        // FN01: Select the drawing planes from bitmask N (XO-CHIP)
+                        self.planes = x as u8 & 0x03;
+                    }
+                    0x0002 if opcode == 0xF002 => {
+                        // F002: Load the 16-byte audio pattern from memory starting at address I (XO-CHIP)
+                        let mut pattern = [0; 16];
+                        for (offset, byte) in pattern.iter_mut().enumerate() {
+                            *byte = self.load(self.reg_index as usize + offset)?;
+                        }
+                        self.audio_pattern = Some(pattern);
+                    }
+                    0x0007 => {
+                        // FX07: Store the current value of the delay timer in register VX
+                        self.registers[x] = self.delay_timer;
+                    }
+                    0x000A => {
+                        // FX0A: Wait for a keypress and store the result in register VX
+                        // only keys pressed from now on or still held down count
+                        self.last_pressed_key = None;
+                        self.key_wait = Some(KeyWait { register: x, key: None });
+                    }
+                    0x0015 => {
+                        // FX15: Set the delay timer to the value of register VX
+                        self.delay_timer = self.registers[x];
+                    }
+                    0x0018 => {
+                        // FX18: Set the sound timer to the value of register VX
+                        self.sound_timer = self.registers[x];
+                    }
+                    0x001E => {
+                        // FX1E: Add the value stored in register VX to register I
+                        self.reg_index = self.reg_index.wrapping_add(self.registers[x] as u16);
+                    }
+                    0x0029 => {
+                        // FX29: Set I to the memory address of the sprite data corresponding to the hexadecimal digit stored in register VX
+                        self.reg_index = MEMORY_FONT_START + (self.registers[x] as u16 & 0x0F) * 5;
+                    }
+                    0x0030 => {
+                        // FX30: Set I to the memory address of the 10-byte sprite data for the digit stored in VX (SUPER-CHIP)
+                        self.reg_index = MEMORY_BIG_FONT_START + (self.registers[x] as u16 & 0x0F) * 10;
+                    }
+                    0x0033 => {
+                        // FX33: Store the binary-coded decimal equivalent of the value stored in register VX at addresses I, I + 1, and I + 2
+                        let x = self.registers[x];
+                        let address = self.reg_index as usize;
+                        self.store(address, x / 100)?;
+                        self.store(address + 1, (x / 10) % 10)?;
+                        self.store(address + 2, x % 10)?;
+                    }
+                    0x0055 => {
+                        // FX55: Store the values of registers V0 to VX inclusive in memory starting at address I, I is set to I + X + 1 after operation²
+                        for i in 0..=x {
+                            self.store(self.reg_index as usize + i, self.registers[i])?;
+                        }
+                        if self.quirks.load_store_increments_i {
+                            self.reg_index = self.reg_index.wrapping_add(x as u16 + 1);
+                        }
+                    }
+                    0x0065 => {
+                        // FX65: Fill registers V0 to VX inclusive with the values stored in memory starting at address I, I is set to I + X + 1 after operation²
+                        for i in 0..=x {
+                            self.registers[i] = self.load(self.reg_index as usize + i)?;
+                        }
+                        if self.quirks.load_store_increments_i {
+                            self.reg_index = self.reg_index.wrapping_add(x as u16 + 1);
+                        }
+                    }
+                    0x003A => {
+                        // FX3A: Set the audio pattern pitch to the value of register VX (XO-CHIP)
+                        self.pitch = self.registers[x];
+                    }
+                    0x0075 => {
+                        // FX75: Store V0 to VX inclusive in the RPL user flags, X < 8 (SUPER-CHIP)
+                        let n = (x + 1).min(RPL_FLAGS);
+                        self.rpl_flags[..n].copy_from_slice(&self.registers[..n]);
+                    }
+                    0x0085 => {
+                        // FX85: Fill V0 to VX inclusive from the RPL user flags, X < 8 (SUPER-CHIP)
+                        let n = (x + 1).min(RPL_FLAGS);
+                        self.registers[..n].copy_from_slice(&self.rpl_flags[..n]);
+                    }
+                    _ => return Err(Fault::UnknownOpcode)
+                }
+            }
+            _ => return Err(Fault::UnknownOpcode)
+        }
+        Ok(())
+    }
+}
+
+/// Why an instruction failed, before the address and opcode are attached.
+enum Fault {
+    UnknownOpcode,
+    StackOverflow,
+    StackUnderflow,
+    MemoryOutOfBounds(usize),
+}
+
+impl Fault {
+    fn at(self, pc: u16, opcode: u16) -> Chip8Error {
+        match self {
+            Fault::UnknownOpcode => Chip8Error::UnknownOpcode { pc, opcode },
+            Fault::StackOverflow => Chip8Error::StackOverflow { pc, opcode },
+            Fault::StackUnderflow => Chip8Error::StackUnderflow { pc, opcode },
+            Fault::MemoryOutOfBounds(address) => Chip8Error::MemoryOutOfBounds { pc, opcode, address },
+        }
+    }
+}
+
+
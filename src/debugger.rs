@@ -0,0 +1,314 @@
+use std::io::{self, BufRead, Write};
+
+use crate::cpu::Chip8;
+use crate::disasm;
+
+const HELP: &str = "\
+s, step [N]            execute N instructions (default 1)
+c, continue            run until a breakpoint is hit
+b, break <Addr>        break when PC reaches Addr
+bo <Pattern>           break on an opcode pattern, hex digits match and anything else is a wildcard, e.g. DXYN or 8..E
+bl                     list breakpoints
+bd <Index>             delete a breakpoint
+r, regs                print registers, I, PC, stack and timers
+m, mem <Addr> [Len]    dump Len bytes of memory starting at Addr (default 0x40)
+dis [Addr] [N]         disassemble N instructions starting at Addr (default PC and 0x10)
+set <Reg> <Value>      set V0-VF, I, PC, DT or ST
+w, write <Addr> <Byte>...  write bytes to memory
+q, quit                run to the end of the frame and exit
+All numbers are hexadecimal. An empty line repeats the last command.";
+
+const SET_USAGE: &str = "Usage: set <Reg> <Value>, at most FF for V0-VF, DT and ST and FFFF for I and PC";
+
+enum Breakpoint {
+    Address(u16),
+    Opcode { mask: u16, value: u16, pattern: String },
+}
+
+impl Breakpoint {
+    fn parse_pattern(pattern: &str) -> Option<Breakpoint> {
+        if pattern.chars().count() != 4 {
+            return None;
+        }
+        let mut mask = 0;
+        let mut value = 0;
+        for c in pattern.chars() {
+            mask <<= 4;
+            value <<= 4;
+            if let Some(digit) = c.to_digit(16) {
+                mask |= 0xF;
+                value |= digit as u16;
+            }
+        }
+        Some(Breakpoint::Opcode { mask, value, pattern: pattern.to_uppercase() })
+    }
+
+    fn hit(&self, pc: u16, opcode: u16) -> bool {
+        match self {
+            Breakpoint::Address(address) => *address == pc,
+            Breakpoint::Opcode { mask, value, .. } => opcode & mask == *value,
+        }
+    }
+}
+
+/// An interactive debugger reading commands from stdin.
+///
+/// Attach it to a [`crate::frontend::Frontend`]; it gets control before every instruction and
+/// stops at the first one, at breakpoints and after single steps.
+pub struct Debugger {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    breakpoints: Vec<Breakpoint>,
+    // instructions left to execute before stopping, None while continuing
+    steps_left: Option<u32>,
+    last_command: String,
+    quit: bool,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::with_io(Box::new(io::BufReader::new(io::stdin())), Box::new(io::stdout()))
+    }
+
+    pub fn with_io(input: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
+        Debugger {
+            input,
+            output,
+            breakpoints: Vec::new(),
+            steps_left: Some(0),
+            last_command: String::from("step"),
+            quit: false,
+        }
+    }
+
+    /// Whether the quit command was given. The debugger stops no more once it was, and the frontend
+    /// ends the session after the frame.
+    pub fn quit_requested(&self) -> bool {
+        self.quit
+    }
+
+    /// Called before every instruction. Stops and runs the command prompt when a step count runs
+    /// out or a breakpoint is hit.
+    pub fn before_step(&mut self, chip8: &mut Chip8) {
+        let pc = chip8.pc();
+        let opcode = Self::opcode_at(chip8, pc);
+        let stop = match self.steps_left {
+            Some(0) => true,
+            Some(n) => {
+                self.steps_left = Some(n - 1);
+                false
+            }
+            None => match self.breakpoints.iter().position(|b| b.hit(pc, opcode)) {
+                Some(index) => {
+                    self.print(&format!("Breakpoint {} hit", index));
+                    true
+                }
+                None => false,
+            },
+        };
+        if stop {
+            self.print_location(chip8);
+            self.prompt(chip8);
+        }
+    }
+
+    fn prompt(&mut self, chip8: &mut Chip8) {
+        loop {
+            let _ = write!(self.output, "(chip8) ");
+            let _ = self.output.flush();
+            let mut line = String::new();
+            match self.input.read_line(&mut line) {
+                // end of input, run on without the debugger stopping again
+                Ok(0) | Err(_) => {
+                    self.breakpoints.clear();
+                    self.steps_left = None;
+                    return;
+                }
+                Ok(_) => {}
+            }
+            let line = match line.trim() {
+                "" => self.last_command.clone(),
+                line => line.to_string(),
+            };
+            self.last_command = line.clone();
+            if self.execute(&line, chip8) {
+                return;
+            }
+        }
+    }
+
+    /// Runs one command, returns true if execution should resume.
+    fn execute(&mut self, line: &str, chip8: &mut Chip8) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let args = &words[1..];
+        match words[0] {
+            "s" | "step" => match args.first().map(|n| n.parse::<u32>()) {
+                None => self.steps_left = Some(0),
+                Some(Ok(n)) if n > 0 => self.steps_left = Some(n - 1),
+                _ => {
+                    self.print("Usage: step [N]");
+                    return false;
+                }
+            },
+            "c" | "continue" => self.steps_left = None,
+            "b" | "break" => match args.first().and_then(|a| parse_hex(a)) {
+                Some(address) if address < chip8.memory().len() => {
+                    self.breakpoints.push(Breakpoint::Address(address as u16));
+                    self.print(&format!("Breakpoint {} at {:04X}", self.breakpoints.len() - 1, address));
+                }
+                _ => self.print("Usage: break <Addr>"),
+            },
+            "bo" => match args.first().and_then(|p| Breakpoint::parse_pattern(p)) {
+                Some(breakpoint) => {
+                    self.breakpoints.push(breakpoint);
+                    self.print(&format!("Breakpoint {} on {}", self.breakpoints.len() - 1, args[0].to_uppercase()));
+                }
+                None => self.print("Usage: bo <Pattern>, e.g. bo DXYN"),
+            },
+            "bl" => {
+                let lines: Vec<String> = self.breakpoints.iter().enumerate()
+                    .map(|(i, b)| match b {
+                        Breakpoint::Address(address) => format!("{}: at {:04X}", i, address),
+                        Breakpoint::Opcode { pattern, .. } => format!("{}: on {}", i, pattern),
+                    })
+                    .collect();
+                for line in lines {
+                    self.print(&line);
+                }
+            }
+            "bd" => match args.first().and_then(|a| a.parse::<usize>().ok()) {
+                Some(index) if index < self.breakpoints.len() => {
+                    self.breakpoints.remove(index);
+                }
+                _ => self.print("Usage: bd <Index>"),
+            },
+            "r" | "regs" => self.print_registers(chip8),
+            "m" | "mem" => match args.first().and_then(|a| parse_hex(a)) {
+                Some(address) if address < chip8.memory().len() => {
+                    let len = args.get(1).and_then(|a| parse_hex(a)).unwrap_or(0x40);
+                    self.print_memory(chip8, address, len);
+                }
+                _ => self.print("Usage: mem <Addr> [Len]"),
+            },
+            "dis" => {
+                let address = args.first().and_then(|a| parse_hex(a)).unwrap_or(chip8.pc() as usize);
+                let count = args.get(1).and_then(|a| parse_hex(a)).unwrap_or(0x10);
+                self.print_disassembly(chip8, address, count);
+            }
+            "set" => match (args.first(), args.get(1).and_then(|v| parse_hex(v))) {
+                (Some(register), Some(value)) => self.set(chip8, register, value),
+                _ => self.print(SET_USAGE),
+            },
+            "w" | "write" => {
+                let address = args.first().and_then(|a| parse_hex(a));
+                let bytes: Option<Vec<u8>> = args.iter().skip(1)
+                    .map(|b| parse_hex(b).filter(|b| *b <= 0xFF).map(|b| b as u8))
+                    .collect();
+                let end = address.zip(bytes.as_ref()).and_then(|(address, bytes)| address.checked_add(bytes.len()));
+                match (address, bytes, end) {
+                    (Some(address), Some(bytes), Some(end)) if end <= chip8.memory().len() => {
+                        chip8.memory_mut()[address..end].copy_from_slice(&bytes);
+                    }
+                    _ => self.print("Usage: write <Addr> <Byte>..."),
+                }
+            }
+            "q" | "quit" => {
+                self.quit = true;
+                self.breakpoints.clear();
+                self.steps_left = None;
+                return true;
+            }
+            "h" | "help" => self.print(HELP),
+            _ => self.print("Unknown command, type help for a list"),
+        }
+        matches!(words[0], "s" | "step" | "c" | "continue")
+    }
+
+    fn set(&mut self, chip8: &mut Chip8, register: &str, value: usize) {
+        let register = register.to_uppercase();
+        // values that do not fit the register are refused rather than truncated
+        let (byte, word) = (u8::try_from(value).ok(), u16::try_from(value).ok());
+        match (register.as_str(), byte, word) {
+            ("I", _, Some(value)) => chip8.set_index(value),
+            ("PC", _, Some(value)) => chip8.set_pc(value),
+            ("DT", Some(value), _) => chip8.set_delay_timer(value),
+            ("ST", Some(value), _) => chip8.set_sound_timer(value),
+            ("I" | "PC" | "DT" | "ST", _, _) => self.print(SET_USAGE),
+            _ => match (register.strip_prefix('V').and_then(parse_hex), byte) {
+                (Some(x), Some(value)) if x < 16 => chip8.set_register(x, value),
+                (Some(x), None) if x < 16 => self.print(SET_USAGE),
+                _ => self.print("Unknown register"),
+            },
+        }
+    }
+
+    fn print_location(&mut self, chip8: &Chip8) {
+        self.print_disassembly(chip8, chip8.pc() as usize, 1);
+    }
+
+    fn print_disassembly(&mut self, chip8: &Chip8, mut address: usize, count: usize) {
+        if address >= chip8.memory().len() {
+            self.print("Address out of memory");
+            return;
+        }
+        for _ in 0..count {
+            let memory = &chip8.memory()[address..];
+            let (text, len) = disasm::decode(memory).unwrap_or_else(|| (String::from("???"), 2));
+            let bytes: Vec<String> = memory.iter().take(len).map(|b| format!("{:02X}", b)).collect();
+            let line = format!("{:04X}: {:<11} {}", address, bytes.join(" "), text);
+            self.print(&line);
+            address += len;
+            if address >= chip8.memory().len() {
+                break;
+            }
+        }
+    }
+
+    fn print_registers(&mut self, chip8: &Chip8) {
+        let registers: Vec<String> = chip8.registers().iter().enumerate()
+            .map(|(i, v)| format!("V{:X}={:02X}", i, v))
+            .collect();
+        self.print(&registers[..8].join(" "));
+        self.print(&registers[8..].join(" "));
+        let line = format!("PC={:04X} I={:04X} DT={:02X} ST={:02X}{}",
+                           chip8.pc(), chip8.index(), chip8.delay_timer(), chip8.sound_timer(),
+                           if chip8.waiting_for_key() { " (waiting for key)" } else { "" });
+        self.print(&line);
+        let stack: Vec<String> = chip8.stack().iter().rev().map(|a| format!("{:04X}", a)).collect();
+        let line = format!("Stack ({} of {}, top first): {}", chip8.stack().len(), chip8.stack_depth(), stack.join(" "));
+        self.print(&line);
+    }
+
+    fn print_memory(&mut self, chip8: &Chip8, address: usize, len: usize) {
+        let memory = chip8.memory();
+        let end = address.saturating_add(len).min(memory.len());
+        for start in (address..end).step_by(16) {
+            let bytes: Vec<String> = memory[start..(start + 16).min(end)].iter()
+                .map(|b| format!("{:02X}", b))
+                .collect();
+            let line = format!("{:04X}: {}", start, bytes.join(" "));
+            self.print(&line);
+        }
+    }
+
+    fn opcode_at(chip8: &Chip8, address: u16) -> u16 {
+        let memory = chip8.memory();
+        let byte = |a: usize| memory.get(a).copied().unwrap_or(0) as u16;
+        byte(address as usize) << 8 | byte(address as usize + 1)
+    }
+
+    fn print(&mut self, line: &str) {
+        let _ = writeln!(self.output, "{}", line);
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_hex(s: &str) -> Option<usize> {
+    let s = s.trim_start_matches("0x").trim_start_matches("0X");
+    usize::from_str_radix(s, 16).ok()
+}
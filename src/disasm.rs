@@ -0,0 +1,234 @@
+//! Disassembler producing classic mnemonics such as `LD V1, 0x20`.
+//!
+//! [`mnemonic`] decodes a single instruction. [`disassemble`] lists a whole ROM: it follows the
+//! control flow from the entry point to tell code from data, gives jump, call and `LD I` targets
+//! labels, and prints data bytes with a picture of the sprite row they encode.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::error::Chip8Error;
+use crate::opcode::Opcode;
+
+/// Decodes the instruction at the start of `bytes`, returning its mnemonic and its length in
+/// bytes (4 for the XO-CHIP `F000 NNNN`, 2 otherwise). `None` if it is not a known instruction.
+pub fn decode(bytes: &[u8]) -> Option<(String, usize)> {
+    decode_with(bytes, &|address| format!("0x{:03X}", address))
+}
+
+/// The mnemonic of a single 2-byte instruction, or `None` if it is not a known instruction.
+pub fn mnemonic(opcode: u16) -> Option<String> {
+    decode(&opcode.to_be_bytes()).map(|(text, _)| text)
+}
+
+fn word(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some((*bytes.get(offset)? as u16) << 8 | *bytes.get(offset + 1)? as u16)
+}
+
+fn decode_with(bytes: &[u8], address: &dyn Fn(u16) -> String) -> Option<(String, usize)> {
+    let opcode = Opcode(word(bytes, 0)?);
+    let (x, y, n, nn, nnn) = (opcode.x(), opcode.y(), opcode.n(), opcode.nn(), opcode.nnn());
+    let text = match opcode.0 & 0xF000 {
+        0x0000 => match opcode.0 {
+            0x00C0..=0x00CF => format!("SCD {}", n),
+            0x00D0..=0x00DF => format!("SCU {}", n),
+            0x00E0 => "CLS".to_string(),
+            0x00EE => "RET".to_string(),
+            0x00FB => "SCR".to_string(),
+            0x00FC => "SCL".to_string(),
+            0x00FD => "EXIT".to_string(),
+            0x00FE => "LOW".to_string(),
+            0x00FF => "HIGH".to_string(),
+            _ => return None,
+        },
+        0x1000 => format!("JP {}", address(nnn)),
+        0x2000 => format!("CALL {}", address(nnn)),
+        0x3000 => format!("SE V{:X}, 0x{:02X}", x, nn),
+        0x4000 => format!("SNE V{:X}, 0x{:02X}", x, nn),
+        0x5000 => match n {
+            0x0 => format!("SE V{:X}, V{:X}", x, y),
+            0x2 => format!("SAVE V{:X} - V{:X}", x, y),
+            0x3 => format!("LOAD V{:X} - V{:X}", x, y),
+            _ => return None,
+        },
+        0x6000 => format!("LD V{:X}, 0x{:02X}", x, nn),
+        0x7000 => format!("ADD V{:X}, 0x{:02X}", x, nn),
+        0x8000 => {
+            let name = match n {
+                0x0 => "LD",
+                0x1 => "OR",
+                0x2 => "AND",
+                0x3 => "XOR",
+                0x4 => "ADD",
+                0x5 => "SUB",
+                0x6 => "SHR",
+                0x7 => "SUBN",
+                0xE => "SHL",
+                _ => return None,
+            };
+            format!("{} V{:X}, V{:X}", name, x, y)
+        }
+        0x9000 if n == 0 => format!("SNE V{:X}, V{:X}", x, y),
+        0xA000 => format!("LD I, {}", address(nnn)),
+        0xB000 => format!("JP V0, {}", address(nnn)),
+        0xC000 => format!("RND V{:X}, 0x{:02X}", x, nn),
+        0xD000 => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        0xE000 => match nn {
+            0x9E => format!("SKP V{:X}", x),
+            0xA1 => format!("SKNP V{:X}", x),
+            _ => return None,
+        },
+        0xF000 => match nn {
+            0x00 if x == 0 => return Some((format!("LD I, {}", address(word(bytes, 2)?)), 4)),
+            0x01 => format!("PLANE {}", x),
+            0x02 if x == 0 => "AUDIO".to_string(),
+            0x07 => format!("LD V{:X}, DT", x),
+            0x0A => format!("LD V{:X}, K", x),
+            0x15 => format!("LD DT, V{:X}", x),
+            0x18 => format!("LD ST, V{:X}", x),
+            0x1E => format!("ADD I, V{:X}", x),
+            0x29 => format!("LD F, V{:X}", x),
+            0x30 => format!("LD HF, V{:X}", x),
+            0x33 => format!("LD B, V{:X}", x),
+            0x3A => format!("PITCH V{:X}", x),
+            0x55 => format!("LD [I], V{:X}", x),
+            0x65 => format!("LD V{:X}, [I]", x),
+            0x75 => format!("LD R, V{:X}", x),
+            0x85 => format!("LD V{:X}, R", x),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some((text, 2))
+}
+
+/// How a line of the listing was classified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineKind {
+    /// A reachable instruction and its mnemonic.
+    Code(String),
+    /// A byte that is never executed.
+    Data,
+}
+
+/// One line of a [`Disassembly`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub label: Option<String>,
+    pub kind: LineKind,
+}
+
+/// A disassembled ROM. Its `Display` implementation prints the listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disassembly {
+    pub lines: Vec<Line>,
+}
+
+/// Disassembles `rom`, loaded at address `base`, starting execution at `base`. Fails if `rom` runs
+/// past the end of the 64KB address space.
+pub fn disassemble(rom: &[u8], base: u16) -> Result<Disassembly, Chip8Error> {
+    let max = 0x10000 - base as usize;
+    if rom.len() > max {
+        return Err(Chip8Error::RomTooLarge { size: rom.len(), max });
+    }
+    let in_rom = |address: u16| address >= base && ((address - base) as usize) < rom.len();
+    let at = |address: u16| &rom[(address - base) as usize..];
+
+    // follow every path from the entry point to find the instructions and the addresses they use
+    let mut code = BTreeMap::new();
+    let mut labels = BTreeMap::new();
+    let mut pending = vec![base];
+    while let Some(address) = pending.pop() {
+        if !in_rom(address) || code.contains_key(&address) {
+            continue;
+        }
+        let (_, len) = match decode(at(address)) {
+            Some(decoded) => decoded,
+            None => continue,
+        };
+        code.insert(address, len);
+        let opcode = Opcode(word(at(address), 0).unwrap_or(0));
+        let next = address.wrapping_add(len as u16);
+        let skip_target = || {
+            // a skip passes over the following instruction, which may be 4 bytes long
+            let long = in_rom(next) && word(at(next), 0) == Some(0xF000);
+            next.wrapping_add(if long { 4 } else { 2 })
+        };
+        match opcode.0 & 0xF000 {
+            0x0000 if opcode.0 == 0x00EE || opcode.0 == 0x00FD => {}
+            0x1000 => {
+                labels.entry(opcode.nnn()).or_insert_with(|| format!("label_{:03X}", opcode.nnn()));
+                pending.push(opcode.nnn());
+            }
+            0x2000 => {
+                labels.insert(opcode.nnn(), format!("sub_{:03X}", opcode.nnn()));
+                pending.push(opcode.nnn());
+                pending.push(next);
+            }
+            // the target of a computed jump is unknown
+            0xB000 => {}
+            0x3000 | 0x4000 | 0x5000 | 0x9000 | 0xE000 => {
+                pending.push(next);
+                pending.push(skip_target());
+            }
+            0xA000 => {
+                labels.entry(opcode.nnn()).or_insert_with(|| format!("data_{:03X}", opcode.nnn()));
+                pending.push(next);
+            }
+            0xF000 if opcode.0 == 0xF000 => {
+                if let Some(target) = word(at(address), 2) {
+                    labels.entry(target).or_insert_with(|| format!("data_{:03X}", target));
+                }
+                pending.push(next);
+            }
+            _ => pending.push(next),
+        }
+    }
+
+    // split the ROM into instructions and single data bytes
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    while offset < rom.len() {
+        let address = base + offset as u16;
+        let len = code.get(&address).copied().unwrap_or(1);
+        starts.push((address, code.contains_key(&address), len));
+        offset += len;
+    }
+    // only addresses that start a line can be labelled
+    labels.retain(|address, _| starts.binary_search_by_key(address, |(a, _, _)| *a).is_ok());
+
+    let label_or_address = |address: u16| labels.get(&address).cloned().unwrap_or_else(|| format!("0x{:03X}", address));
+    let lines = starts.into_iter()
+        .map(|(address, is_code, len)| {
+            let bytes = at(address)[..len].to_vec();
+            let kind = if is_code {
+                LineKind::Code(decode_with(at(address), &label_or_address).unwrap().0)
+            } else {
+                LineKind::Data
+            };
+            Line { address, bytes, label: labels.get(&address).cloned(), kind }
+        })
+        .collect();
+    Ok(Disassembly { lines })
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            if let Some(ref label) = line.label {
+                writeln!(f, "{}:", label)?;
+            }
+            let bytes: Vec<String> = line.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            match line.kind {
+                LineKind::Code(ref text) => writeln!(f, "    {:04X}  {:<11} {}", line.address, bytes.join(" "), text)?,
+                LineKind::Data => {
+                    let sprite: String = (0..8).map(|bit| if line.bytes[0] & (0x80 >> bit) != 0 { '#' } else { '.' }).collect();
+                    writeln!(f, "    {:04X}  {:<11} DB 0x{:02X}        ; {}", line.address, bytes.join(" "), line.bytes[0], sprite)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
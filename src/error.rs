@@ -0,0 +1,44 @@
+use std::error::Error;
+use std::fmt;
+
+/// A fault raised by a running program. `pc` is the address of the faulting instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chip8Error {
+    /// The instruction is not part of any supported instruction set.
+    UnknownOpcode { pc: u16, opcode: u16 },
+    /// A subroutine call exceeded the call stack.
+    StackOverflow { pc: u16, opcode: u16 },
+    /// A return was executed with no subroutine to return from.
+    StackUnderflow { pc: u16, opcode: u16 },
+    /// The instruction read or wrote past the end of memory.
+    MemoryOutOfBounds { pc: u16, opcode: u16, address: usize },
+    /// The ROM does not fit in program memory.
+    RomTooLarge { size: usize, max: usize },
+    /// A save state could not be loaded.
+    InvalidState { reason: String },
+    /// An input movie could not be loaded.
+    InvalidMovie { reason: String },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::UnknownOpcode { pc, opcode } =>
+                write!(f, "Unsupported instruction: {:04X} at {:04X}", opcode, pc),
+            Chip8Error::StackOverflow { pc, opcode } =>
+                write!(f, "Stack overflow: {:04X} at {:04X}", opcode, pc),
+            Chip8Error::StackUnderflow { pc, opcode } =>
+                write!(f, "Stack underflow: {:04X} at {:04X}", opcode, pc),
+            Chip8Error::MemoryOutOfBounds { pc, opcode, address } =>
+                write!(f, "Memory access out of bounds: {:04X} at {:04X} accessed {:04X}", opcode, pc, address),
+            Chip8Error::RomTooLarge { size, max } =>
+                write!(f, "ROM too large: {} bytes, at most {} bytes fit in program memory", size, max),
+            Chip8Error::InvalidState { reason } =>
+                write!(f, "Invalid save state: {}", reason),
+            Chip8Error::InvalidMovie { reason } =>
+                write!(f, "Invalid movie: {}", reason),
+        }
+    }
+}
+
+impl Error for Chip8Error {}
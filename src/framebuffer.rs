@@ -0,0 +1,137 @@
+use std::fmt;
+
+/// Width of the screen in low resolution mode.
+pub const LORES_WIDTH: usize = 64;
+/// Height of the screen in low resolution mode.
+pub const LORES_HEIGHT: usize = 32;
+/// Width of the screen in SUPER-CHIP high resolution mode.
+pub const HIRES_WIDTH: usize = 128;
+/// Height of the screen in SUPER-CHIP high resolution mode.
+pub const HIRES_HEIGHT: usize = 64;
+
+/// The screen contents, one byte per pixel in row-major order.
+///
+/// Each pixel is a bitmask of the planes it is set in: plane 1 is bit 0 and plane 2 is bit 1.
+/// Programs that do not select planes only ever draw to plane 1, so their pixels are 0 or 1.
+/// XO-CHIP programs can draw to both and produce four colours.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameBuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl FrameBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        FrameBuffer {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    /// A framebuffer with the given contents, or `None` if `pixels` does not hold
+    /// `width * height` pixels.
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<u8>) -> Option<Self> {
+        if pixels.len() != width * height {
+            return None;
+        }
+        Some(FrameBuffer { width, height, pixels })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Iterates over the rows of the screen, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item=&[u8]> {
+        self.pixels.chunks(self.width)
+    }
+
+    /// Unsets the given planes of every pixel.
+    pub fn clear(&mut self, planes: u8) {
+        self.pixels.iter_mut().for_each(|p| *p &= !planes);
+    }
+
+    /// Changes the resolution, clearing the screen.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width * height];
+    }
+
+    /// XORs `plane` into the pixel at (x, y), wrapping around the screen edges.
+    /// Returns true if the pixel was set in that plane and is now unset.
+    pub fn flip(&mut self, x: usize, y: usize, plane: u8) -> bool {
+        let position = (y % self.height) * self.width + x % self.width;
+        self.pixels[position] ^= plane;
+        self.pixels[position] & plane == 0
+    }
+
+    /// Moves the given planes down by `n` rows, filling the top with blank rows.
+    pub fn scroll_down(&mut self, n: usize, planes: u8) {
+        self.scroll(0, n as isize, planes);
+    }
+
+    /// Moves the given planes up by `n` rows, filling the bottom with blank rows.
+    pub fn scroll_up(&mut self, n: usize, planes: u8) {
+        self.scroll(0, -(n as isize), planes);
+    }
+
+    /// Moves the given planes right by `n` columns, filling the left with blank columns.
+    pub fn scroll_right(&mut self, n: usize, planes: u8) {
+        self.scroll(n as isize, 0, planes);
+    }
+
+    /// Moves the given planes left by `n` columns, filling the right with blank columns.
+    pub fn scroll_left(&mut self, n: usize, planes: u8) {
+        self.scroll(-(n as isize), 0, planes);
+    }
+
+    fn scroll(&mut self, dx: isize, dy: isize, planes: u8) {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let source = self.pixels.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = (x - dx, y - dy);
+                let moved = if (0..width).contains(&sx) && (0..height).contains(&sy) {
+                    source[(sy * width + sx) as usize] & planes
+                } else {
+                    0
+                };
+                let pixel = &mut self.pixels[(y * width + x) as usize];
+                *pixel = (*pixel & !planes) | moved;
+            }
+        }
+    }
+}
+
+/// Prints one line per row and one character per pixel: `.` for unset pixels, `#` for plane 1,
+/// `+` for plane 2 and `@` for both.
+impl fmt::Display for FrameBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            let line: String = row.iter().map(|p| ['.', '#', '+', '@'][*p as usize & 0x03]).collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new(LORES_WIDTH, LORES_HEIGHT)
+    }
+}
@@ -0,0 +1,309 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cpu::Chip8;
+use crate::debugger::Debugger;
+use crate::error::Chip8Error;
+use crate::framebuffer::FrameBuffer;
+use crate::movie::{MoviePlayer, MovieRecorder};
+use crate::rewind::Rewind;
+
+const FPS: u64 = 60;
+// 60hz
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / FPS);
+// how far start() may fall behind before it gives up catching up, e.g. after the debugger stopped
+const MAX_LAG: Duration = Duration::from_millis(250);
+const MAX_INSTRUCTIONS_PER_FRAME: u32 = 100_000;
+
+/// Receives the framebuffer once per frame. Its resolution changes when a SUPER-CHIP program
+/// switches between 64x32 and 128x64.
+pub trait VideoSink {
+    fn present(&mut self, frame_buffer: &FrameBuffer);
+
+    /// Switches between a window and fullscreen, if the sink has a window.
+    fn toggle_fullscreen(&mut self) {}
+}
+
+/// Receives the state of the beeper once per frame.
+pub trait AudioSink {
+    fn set_beep(&mut self, on: bool);
+
+    /// Receives the XO-CHIP audio pattern to play instead of a plain beep, and its playback rate
+    /// in bits per second. Only called once the program has loaded a pattern.
+    fn set_pattern(&mut self, _pattern: &[u8; 16], _rate: f32) {}
+}
+
+/// Requests to the emulator rather than to the program, mostly bound to host hotkeys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Write the machine state to the state file.
+    SaveState,
+    /// Restore the machine state from the state file.
+    LoadState,
+    /// Step back one frame in the rewind history. Reported every frame while the hotkey is held.
+    Rewind,
+    /// Run about a quarter more instructions per frame.
+    SpeedUp,
+    /// Run about a fifth fewer instructions per frame.
+    SpeedDown,
+    /// Stop [`Frontend::start`], e.g. because the window was closed.
+    Quit,
+    /// The window lost the input focus.
+    FocusLost,
+    /// The window got the input focus back.
+    FocusGained,
+    /// Switch between a window and fullscreen.
+    ToggleFullscreen,
+}
+
+/// Reports the state of the 16 hex keys once per frame.
+pub trait InputSource {
+    /// Processes pending host input and writes the current state of every hex key into `keys`.
+    fn poll(&mut self, keys: &mut [bool; 16]);
+
+    /// Returns the commands whose hotkeys were pressed since the last call. Called after `poll`.
+    fn commands(&mut self) -> Vec<Command> {
+        Vec::new()
+    }
+}
+
+/// Drives a [`Chip8`] core with a video sink, an audio sink and an input source.
+pub struct Frontend<V, A, I> {
+    video: V,
+    audio: A,
+    input: I,
+    keys: [bool; 16],
+    debugger: Option<Debugger>,
+    state_file: Option<PathBuf>,
+    rewind: Option<Rewind>,
+    recorder: Option<MovieRecorder>,
+    player: Option<MoviePlayer>,
+    pause_on_focus_loss: bool,
+    paused: bool,
+    quit: bool,
+}
+
+impl<V: VideoSink, A: AudioSink, I: InputSource> Frontend<V, A, I> {
+    pub fn new(video: V, audio: A, input: I) -> Self {
+        Frontend {
+            video,
+            audio,
+            input,
+            keys: [false; 16],
+            debugger: None,
+            state_file: None,
+            rewind: None,
+            recorder: None,
+            player: None,
+            pause_on_focus_loss: false,
+            paused: false,
+            quit: false,
+        }
+    }
+
+    /// Sets the file the save and load state hotkeys use. They do nothing until one is set.
+    pub fn set_state_file(&mut self, path: PathBuf) {
+        self.state_file = Some(path);
+    }
+
+    /// Keeps the state of every frame of the last `seconds` seconds so the rewind hotkey can step
+    /// back through them.
+    pub fn enable_rewind(&mut self, seconds: u32) {
+        self.rewind = Some(Rewind::new(seconds as usize * FPS as usize));
+    }
+
+    /// Records the keys of every frame into `recorder`.
+    pub fn record(&mut self, recorder: MovieRecorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Ends the movie started by [`Frontend::record`], flushing the frames recorded so far.
+    pub fn stop_recording(&mut self) -> io::Result<()> {
+        match self.recorder.take() {
+            Some(recorder) => recorder.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Takes the keys of every frame from `player` instead of the input source until the movie ends.
+    pub fn replay(&mut self, player: MoviePlayer) {
+        self.player = Some(player);
+    }
+
+    /// Whether a movie passed to [`Frontend::replay`] is still playing.
+    pub fn replaying(&self) -> bool {
+        self.player.is_some()
+    }
+
+    /// Stops running the core while the window does not have the input focus.
+    pub fn set_pause_on_focus_loss(&mut self, enabled: bool) {
+        self.pause_on_focus_loss = enabled;
+        self.paused &= enabled;
+    }
+
+    /// Gives `debugger` control before every instruction.
+    pub fn attach_debugger(&mut self, debugger: Debugger) {
+        self.debugger = Some(debugger);
+    }
+
+    /// Polls input, runs one frame of the core and hands its output to the sinks. While paused
+    /// only the commands are handled and the core stays silent.
+    pub fn run_frame(&mut self, chip8: &mut Chip8) -> Result<(), Chip8Error> {
+        self.input.poll(&mut self.keys);
+        if self.paused {
+            for command in self.input.commands() {
+                self.on_command(chip8, command);
+            }
+            self.audio.set_beep(false);
+            self.video.present(chip8.frame_buffer());
+            return Ok(());
+        }
+        if let Some(ref mut player) = self.player {
+            match player.next_keys() {
+                Some(keys) => self.keys = keys,
+                None => self.player = None,
+            }
+        }
+        for (key, pressed) in self.keys.iter().enumerate() {
+            chip8.set_key(key as u8, *pressed);
+        }
+        let commands = self.input.commands();
+        for command in commands.iter() {
+            self.on_command(chip8, *command);
+        }
+        let result = if commands.contains(&Command::Rewind) && !self.movie_active() {
+            self.step_back(chip8);
+            Ok(())
+        } else {
+            let result = match self.debugger {
+                Some(ref mut debugger) => chip8.run_frame_with(|chip8| debugger.before_step(chip8)),
+                None => chip8.run_frame(),
+            };
+            if self.debugger.as_ref().is_some_and(Debugger::quit_requested) {
+                self.quit = true;
+            }
+            if let Some(ref mut rewind) = self.rewind {
+                rewind.push(chip8.save_state());
+            }
+            if let Some(Err(e)) = self.recorder.as_mut().map(|recorder| recorder.record(&self.keys)) {
+                eprintln!("Recording stopped: {}", e);
+                self.recorder = None;
+            }
+            result
+        };
+        if let Some(pattern) = chip8.audio_pattern() {
+            self.audio.set_pattern(pattern, chip8.audio_pattern_rate());
+        }
+        self.audio.set_beep(chip8.sound_active());
+        self.video.present(chip8.frame_buffer());
+        result
+    }
+
+    /// Whether the input source or the debugger asked to end the session. [`Frontend::start`]
+    /// returns once it is set; callers running frames themselves check it after every frame.
+    pub fn quit_requested(&self) -> bool {
+        self.quit
+    }
+
+    /// Whether a movie is being recorded or replayed. Jumping to another state with a state file or
+    /// the rewind history would make it impossible to replay.
+    fn movie_active(&self) -> bool {
+        self.recorder.is_some() || self.player.is_some()
+    }
+
+    /// Replaces the frame with the state of the frame before it, if the history has one.
+    fn step_back(&mut self, chip8: &mut Chip8) {
+        if let Some(state) = self.rewind.as_mut().and_then(|rewind| rewind.step_back()) {
+            if let Err(e) = chip8.load_state(state) {
+                eprintln!("{}", e);
+            }
+        }
+    }
+
+    fn on_command(&mut self, chip8: &mut Chip8, command: Command) {
+        let speed = chip8.instructions_per_frame();
+        match command {
+            Command::LoadState | Command::SpeedUp | Command::SpeedDown if self.movie_active() => {
+                eprintln!("Loading states and changing the speed are disabled while recording or replaying a movie");
+            }
+            Command::SaveState | Command::LoadState => self.on_state_command(chip8, command),
+            Command::SpeedUp => Self::set_speed(chip8, (speed * 5 / 4).max(speed + 1).min(MAX_INSTRUCTIONS_PER_FRAME)),
+            Command::SpeedDown => Self::set_speed(chip8, speed * 4 / 5),
+            // handled by run_frame
+            Command::Rewind => {}
+            Command::Quit => self.quit = true,
+            Command::FocusLost => self.paused = self.pause_on_focus_loss,
+            Command::FocusGained => self.paused = false,
+            Command::ToggleFullscreen => self.video.toggle_fullscreen(),
+        }
+    }
+
+    fn set_speed(chip8: &mut Chip8, instructions_per_frame: u32) {
+        chip8.set_instructions_per_frame(instructions_per_frame);
+        eprintln!("{} instructions per frame", chip8.instructions_per_frame());
+    }
+
+    fn on_state_command(&mut self, chip8: &mut Chip8, command: Command) {
+        let path = match self.state_file {
+            Some(ref path) => path,
+            None => return,
+        };
+        if command == Command::SaveState {
+            match fs::write(path, chip8.save_state()) {
+                Ok(()) => eprintln!("Saved state to {}", path.display()),
+                Err(e) => eprintln!("{}: {}", path.display(), e),
+            }
+        } else {
+            let result = fs::read(path)
+                .map_err(|e| e.to_string())
+                .and_then(|state| chip8.load_state(&state).map_err(|e| e.to_string()));
+            match result {
+                Ok(()) => eprintln!("Loaded state from {}", path.display()),
+                Err(e) => eprintln!("{}: {}", path.display(), e),
+            }
+        }
+    }
+
+    /// Runs frames paced at 60 Hz until the input source or the debugger asks to quit or the
+    /// program faults.
+    pub fn start(&mut self, chip8: &mut Chip8) -> Result<(), Chip8Error> {
+        // frames are scheduled against the clock rather than the end of the previous frame, so a
+        // slow frame is made up for by the next ones and the timers average exactly 60 Hz
+        let mut deadline = Instant::now();
+        while !self.quit {
+            self.run_frame(chip8)?;
+            deadline += FRAME_DURATION;
+            let now = Instant::now();
+            if deadline > now {
+                thread::sleep(deadline - now);
+            } else if now - deadline > MAX_LAG {
+                deadline = now;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Discards every frame.
+pub struct NoVideo;
+
+impl VideoSink for NoVideo {
+    fn present(&mut self, _frame_buffer: &FrameBuffer) {}
+}
+
+/// Discards the beeper state.
+pub struct NoAudio;
+
+impl AudioSink for NoAudio {
+    fn set_beep(&mut self, _on: bool) {}
+}
+
+/// Never presses a key.
+pub struct NoInput;
+
+impl InputSource for NoInput {
+    fn poll(&mut self, _keys: &mut [bool; 16]) {}
+}
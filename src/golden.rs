@@ -0,0 +1,181 @@
+//! Golden image tests: run a ROM headless for a number of frames and compare the screen with a
+//! stored image.
+//!
+//! A golden file is text. A header of `name: value` lines describes the run, a `---` line ends
+//! it and the expected screen follows, printed as by the `Display` implementation of
+//! [`FrameBuffer`]. Lines starting with `#` are comments.
+//!
+//! ```text
+//! # the IBM logo is complete after 20 frames
+//! rom: ../../roms/IBM
+//! frames: 20
+//! ---
+//! ................................................................
+//! ...
+//! ```
+//!
+//! | Name       | Value                                                                          |
+//! |------------|--------------------------------------------------------------------------------|
+//! | `rom`      | the ROM, relative to the golden file (required)                                |
+//! | `frames`   | the number of frames to run (required)                                         |
+//! | `seed`     | the seed of the random numbers used by CXNN (default 0)                        |
+//! | `platform` | `vip`, `chip48`, `schip` or `xochip`, for its quirks and memory size            |
+//! | `quirk`    | a quirk override such as `clip_sprites=off`, may be repeated                   |
+//! | `ipf`      | instructions per frame                                                         |
+//! | `input`    | `<Frame> <Keys>`: from that frame on hold the hex keys listed, e.g. `5` or `46`, or none with `-`; may be repeated |
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cpu::Chip8;
+use crate::framebuffer::FrameBuffer;
+use crate::frontend::{Frontend, NoAudio, NoInput, NoVideo};
+use crate::movie::MoviePlayer;
+use crate::quirks::Platform;
+
+/// A parsed golden file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Golden {
+    pub rom: PathBuf,
+    pub frames: u32,
+    pub seed: u64,
+    pub platform: Option<Platform>,
+    pub quirks: Vec<(String, bool)>,
+    pub instructions_per_frame: Option<u32>,
+    /// The frame from which a key mask is held, bit N for hex key N, in order of frames.
+    pub inputs: Vec<(u32, u16)>,
+    /// The header lines as they were written, kept when the image is replaced.
+    pub header: String,
+    /// The expected screen.
+    pub image: String,
+}
+
+impl Golden {
+    /// Reads and parses the golden file at `path`.
+    pub fn load(path: &Path) -> Result<Golden, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text, path.parent().unwrap_or_else(|| Path::new(".")))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Parses a golden file whose ROM path is relative to `dir`.
+    pub fn parse(text: &str, dir: &Path) -> Result<Golden, String> {
+        let (header, image) = match text.split_once("\n---\n") {
+            Some((header, image)) => (header, image),
+            None => return Err("missing --- line after the header".to_string()),
+        };
+        let mut rom = None;
+        let mut frames = None;
+        let mut golden = Golden {
+            rom: PathBuf::new(),
+            frames: 0,
+            seed: 0,
+            platform: None,
+            quirks: Vec::new(),
+            instructions_per_frame: None,
+            inputs: Vec::new(),
+            header: format!("{}\n", header),
+            image: image.to_string(),
+        };
+        for line in header.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let (name, value) = line.split_once(':').ok_or_else(|| format!("expected name: value, got {}", line))?;
+            let value = value.trim();
+            let invalid = || format!("invalid {}: {}", name, value);
+            match name.trim() {
+                "rom" => rom = Some(dir.join(value)),
+                "frames" => frames = Some(value.parse::<u32>().map_err(|_| invalid())?),
+                "seed" => golden.seed = value.parse().map_err(|_| invalid())?,
+                "platform" => golden.platform = Some(value.parse()?),
+                "quirk" => match value.split_once('=') {
+                    Some((quirk, "on")) => golden.quirks.push((quirk.to_string(), true)),
+                    Some((quirk, "off")) => golden.quirks.push((quirk.to_string(), false)),
+                    _ => return Err(invalid()),
+                },
+                "ipf" => golden.instructions_per_frame = Some(value.parse().map_err(|_| invalid())?),
+                "input" => {
+                    let (frame, keys) = value.split_once(' ').ok_or_else(invalid)?;
+                    let frame = frame.parse::<u32>().map_err(|_| invalid())?;
+                    let mask = match keys.trim() {
+                        "-" => 0,
+                        keys => keys.chars()
+                            .map(|c| c.to_digit(16).map(|key| 1u16 << key))
+                            .sum::<Option<u16>>()
+                            .ok_or_else(invalid)?,
+                    };
+                    golden.inputs.push((frame, mask));
+                }
+                name => return Err(format!("unknown setting {}", name)),
+            }
+        }
+        golden.rom = rom.ok_or("missing rom")?;
+        golden.frames = frames.ok_or("missing frames")?;
+        golden.inputs.sort_by_key(|&(frame, _)| frame);
+        Ok(golden)
+    }
+
+    /// Runs the ROM as described by the header and returns the final screen.
+    pub fn run(&self) -> Result<FrameBuffer, String> {
+        let rom = fs::read(&self.rom).map_err(|e| format!("{}: {}", self.rom.display(), e))?;
+        let mut chip8 = match self.platform {
+            Some(platform) => Chip8::with_platform(platform),
+            None => Chip8::new(),
+        };
+        let mut quirks = *chip8.quirks();
+        for (name, enabled) in self.quirks.iter() {
+            quirks.set(name, *enabled)?;
+        }
+        chip8.set_quirks(quirks);
+        if let Some(instructions) = self.instructions_per_frame {
+            chip8.set_instructions_per_frame(instructions);
+        }
+        chip8.set_seed(self.seed);
+        chip8.init(&rom).map_err(|e| e.to_string())?;
+
+        let mut frontend = Frontend::new(NoVideo, NoAudio, NoInput);
+        frontend.replay(MoviePlayer::new(self.key_frames()));
+        for _ in 0..self.frames {
+            frontend.run_frame(&mut chip8).map_err(|e| e.to_string())?;
+        }
+        Ok(chip8.frame_buffer().clone())
+    }
+
+    /// The key mask of every frame.
+    fn key_frames(&self) -> Vec<u16> {
+        let mut mask = 0;
+        let mut inputs = self.inputs.iter().peekable();
+        (0..self.frames)
+            .map(|frame| {
+                while let Some(&&(_, next)) = inputs.peek().filter(|(from, _)| *from <= frame) {
+                    mask = next;
+                    inputs.next();
+                }
+                mask
+            })
+            .collect()
+    }
+
+    /// Runs the ROM and compares the screen with the image. The error lists the differing rows.
+    pub fn check(&self) -> Result<(), String> {
+        let actual = self.run()?.to_string();
+        if actual == self.image {
+            return Ok(());
+        }
+        let expected: Vec<&str> = self.image.lines().collect();
+        let mut report = String::from("screen differs from the golden image");
+        for (row, line) in actual.lines().enumerate() {
+            let golden_line = expected.get(row).copied().unwrap_or("");
+            if line != golden_line {
+                report.push_str(&format!("\nrow {:2} expected {}\n       actual   {}", row, golden_line, line));
+            }
+        }
+        if expected.len() != actual.lines().count() {
+            report.push_str(&format!("\nexpected {} rows, got {}", expected.len(), actual.lines().count()));
+        }
+        Err(report)
+    }
+
+    /// The golden file with the image replaced by the screen of a new run.
+    pub fn bless(&self) -> Result<String, String> {
+        Ok(format!("{}---\n{}", self.header, self.run()?))
+    }
+}
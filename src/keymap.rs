@@ -0,0 +1,89 @@
+//! Bindings of host keyboard keys and game controller inputs to the 16 hex keys.
+//!
+//! Host keys are named as SDL names their key codes, e.g. `W`, `Up`, `Space`, `Keypad 8` or a
+//! single character such as `é`. Controller buttons are named as in SDL's controller mappings, e.g.
+//! `a`, `dpup` or `leftshoulder`, and stick and trigger directions are an axis followed by `-` or
+//! `+`, e.g. `lefty-` for the left stick pushed up or `righttrigger+`. Names are not
+//! case-sensitive. A hex key can be bound to any number of inputs, an input drives at most one hex
+//! key.
+
+use std::collections::BTreeMap;
+
+/// The layouts bindings start from, the host keys of hex keys 0 to F. All of them put the
+/// COSMAC VIP keypad on the same four rows of keys, the left of the number row and the three
+/// letter rows below it.
+const PRESETS: &[(&str, [&str; 16])] = &[
+    ("qwerty", ["X", "1", "2", "3", "Q", "W", "E", "A", "S", "D", "Z", "C", "4", "R", "F", "V"]),
+    ("qwertz", ["X", "1", "2", "3", "Q", "W", "E", "A", "S", "D", "Y", "C", "4", "R", "F", "V"]),
+    ("azerty", ["X", "&", "é", "\"", "A", "Z", "E", "Q", "S", "D", "W", "C", "'", "R", "F", "V"]),
+    ("dvorak", ["Q", "1", "2", "3", "'", ",", ".", "A", "O", "E", ";", "J", "4", "P", "U", "K"]),
+    ("colemak", ["X", "1", "2", "3", "Q", "W", "F", "A", "R", "S", "Z", "C", "4", "P", "T", "V"]),
+];
+
+/// The preset used unless another one is configured.
+pub const DEFAULT_PRESET: &str = "qwerty";
+
+/// The default controller bindings. The d-pad and the left stick move in the directions of the
+/// hex keypad, 2, 4, 6 and 8, and the face buttons press 5 in its centre and A, 0 and B on the row
+/// below.
+const GAMEPAD: &[(&str, u8)] = &[
+    ("dpup", 0x2), ("lefty-", 0x2),
+    ("dpleft", 0x4), ("leftx-", 0x4),
+    ("dpright", 0x6), ("leftx+", 0x6),
+    ("dpdown", 0x8), ("lefty+", 0x8),
+    ("a", 0x5), ("b", 0x0), ("x", 0xA), ("y", 0xB),
+];
+
+/// Input names, lowercased, and the hex key each of them drives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    keys: BTreeMap<String, u8>,
+}
+
+impl Keymap {
+    /// The bindings of a built-in layout, see [`Keymap::presets`].
+    pub fn preset(name: &str) -> Option<Keymap> {
+        let (_, keys) = PRESETS.iter().find(|(preset, _)| preset.eq_ignore_ascii_case(name))?;
+        let keys = keys.iter()
+            .enumerate()
+            .map(|(hex, key)| (key.to_lowercase(), hex as u8))
+            .collect();
+        Some(Keymap { keys })
+    }
+
+    /// The default bindings of game controllers.
+    pub fn gamepad() -> Keymap {
+        let keys = GAMEPAD.iter().map(|&(input, hex)| (input.to_string(), hex)).collect();
+        Keymap { keys }
+    }
+
+    /// The names of the built-in layouts.
+    pub fn presets() -> impl Iterator<Item=&'static str> {
+        PRESETS.iter().map(|(name, _)| *name)
+    }
+
+    /// Binds hex key `hex` to the inputs `keys`, replacing its current bindings. Inputs bound to
+    /// another hex key are moved to this one.
+    pub fn bind<S: AsRef<str>>(&mut self, hex: u8, keys: &[S]) {
+        self.keys.retain(|_, bound| *bound != hex);
+        for key in keys {
+            self.keys.insert(key.as_ref().to_lowercase(), hex);
+        }
+    }
+
+    /// The hex key driven by the input `name`.
+    pub fn hex_key(&self, name: &str) -> Option<u8> {
+        self.keys.get(&name.to_lowercase()).copied()
+    }
+
+    /// Every bound input, lowercased, and its hex key.
+    pub fn bindings(&self) -> impl Iterator<Item=(&str, u8)> {
+        self.keys.iter().map(|(key, &hex)| (key.as_str(), hex))
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::preset(DEFAULT_PRESET).unwrap()
+    }
+}
@@ -0,0 +1,26 @@
+//! A CHIP-8 interpreter.
+//!
+//! [`cpu::Chip8`] is the interpreter core and has no platform dependencies. It is driven by a
+//! [`frontend::Frontend`], which connects it to a video sink, an audio sink and an input source.
+//! The SDL window, speaker and keyboard frontend lives in [`sdl`] behind the `sdl` feature.
+
+pub mod config;
+pub mod cpu;
+pub mod debugger;
+pub mod disasm;
+pub mod error;
+pub mod framebuffer;
+pub mod frontend;
+pub mod golden;
+pub mod keymap;
+pub mod movie;
+pub mod opcode;
+pub mod palette;
+pub mod phosphor;
+pub mod quirks;
+pub mod random;
+pub mod rewind;
+#[cfg(feature = "sdl")]
+pub mod sdl;
+pub mod state;
+pub mod timing;
@@ -1,28 +1,442 @@
 use std::{env, fs, process};
-use std::io::Read;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
 
-use crate::chip8::cpu::CPU;
+#[cfg(feature = "sdl")]
+use chip8::config::{self, Config};
+use chip8::cpu::{Chip8, DEFAULT_INSTRUCTIONS_PER_FRAME, MAX_STACK_DEPTH, MEMORY_PROGRAM_BASE};
+use chip8::debugger::Debugger;
+use chip8::disasm;
+use chip8::error::Chip8Error;
+use chip8::golden::Golden;
+use chip8::frontend::{AudioSink, Frontend, InputSource, NoAudio, NoInput, NoVideo, VideoSink};
+use chip8::movie::{Movie, MovieHeader, MoviePlayer, MovieRecorder};
+use chip8::quirks::{Platform, Quirks};
+use chip8::random::{RandomSource, VipRandom, Xorshift};
 
-mod chip8;
+const USAGE: &str = "chip8 [options] <Rom>
+chip8 disasm <Rom>
+chip8 test [--bless] <Golden or Directory>...
+
+Options:
+    --headless <Frames>        Run without a window and print the screen after <Frames> frames
+    --platform <Platform>      vip, chip48, schip (SUPER-CHIP 1.1) or xochip
+    --quirk <Name>=<on|off>    Override a single quirk, e.g. --quirk clip_sprites=off
+    --ipf <N>                  Instructions per 60 Hz frame (default 16)
+    --hz <N>                   Instructions per second, rounded to a whole number per frame
+    --stack-depth <N>          Return addresses the call stack has room for, 1 to 16 (default 12 on vip, else 16)
+    --vip-timing               Time instructions like the COSMAC VIP instead of a fixed number per frame
+    --seed <Seed>              Seed the random numbers of CXNN to make runs reproducible
+    --rng <Source>             xorshift (default) or vip, the COSMAC VIP interpreter's routine
+    --debug                    Stop at the first instruction in an interactive debugger on stdin
+    --load-state <File>        Start from a save state instead of the beginning of the ROM
+    --save-state <File>        Write a save state when the run ends
+    --record <File>            Record the keys of every frame into a movie
+    --replay <File>            Replay a movie recorded with the same ROM and options
+    --rewind <Seconds>         Seconds of history the window keeps for rewinding, 0 to disable (default 10)
+    --config <File>            Read the window settings from <File> instead of ~/.config/chip8/config.toml
+    --keys <Preset>            Keyboard layout: qwerty (default), qwertz, azerty, dvorak or colemak
+    --pause-on-focus-loss      Pause while the window does not have the input focus
+    --palette <Name>           default, green, amber, lcd, high-contrast or octo
+    --colors <Colors>          Comma-separated #RRGGBB colours replacing the palette's background, foreground,
+                               XO-CHIP plane 2 and both planes colours, e.g. --colors #000000,#FFFFFF
+    --scale <N>                Initial window size in window pixels per 64x32 pixel (default 16)
+    --integer-scaling          Scale the screen by whole numbers only when the window is resized
+    --fullscreen               Start in fullscreen
+    --persistence <Mode>       Against flicker: off (default), frames:<N> shows pixels set in any of the last N
+                               frames, decay:<Strength> fades pixels out, strength from 0 to below 1
+
+The window also reads settings from <Rom>.toml, see chip8::config for the format.
+In the window, F5 saves the state to <Rom>.state and F9 loads it. Escape quits. F11 toggles fullscreen.
+Hold Backspace to rewind.
+- and = slow down and speed up the CPU.";
+
+struct Options {
+    rom_file: String,
+    headless_frames: Option<u32>,
+    platform: Option<Platform>,
+    quirks: Quirks,
+    instructions_per_frame: u32,
+    vip_timing: bool,
+    stack_depth: Option<usize>,
+    seed: Option<u64>,
+    random: Option<Box<dyn RandomSource>>,
+    debug: bool,
+    load_state: Option<String>,
+    save_state: Option<String>,
+    record: Option<String>,
+    replay: Option<String>,
+    // only the window supports rewinding
+    #[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+    rewind_seconds: u32,
+    #[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+    config: Option<String>,
+    #[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+    key_preset: Option<String>,
+    #[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+    pause_on_focus_loss: bool,
+    #[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+    palette: Option<String>,
+    #[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+    colors: Option<Vec<String>>,
+    #[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+    scale: Option<u32>,
+    #[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+    integer_scaling: bool,
+    #[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+    fullscreen: bool,
+    #[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+    persistence: Option<String>,
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(-1)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(-1)
+}
+
+fn parse_options(args: &[String]) -> Options {
+    let mut rom_file = None;
+    let mut headless_frames = None;
+    let mut platform = None;
+    let mut quirk_overrides = Vec::new();
+    let mut instructions_per_frame = DEFAULT_INSTRUCTIONS_PER_FRAME;
+    let mut vip_timing = false;
+    let mut stack_depth = None;
+    let mut seed = None;
+    let mut random: Option<Box<dyn RandomSource>> = None;
+    let mut debug = false;
+    let mut load_state = None;
+    let mut save_state = None;
+    let mut record = None;
+    let mut replay = None;
+    let mut rewind_seconds = 10;
+    let mut config = None;
+    let mut key_preset = None;
+    let mut pause_on_focus_loss = false;
+    let mut palette = None;
+    let mut colors = None;
+    let mut scale = None;
+    let mut integer_scaling = false;
+    let mut fullscreen = false;
+    let mut persistence = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--headless" => match value().parse::<u32>() {
+                Ok(frames) => headless_frames = Some(frames),
+                Err(_) => usage(),
+            },
+            "--platform" => match value().parse::<Platform>() {
+                Ok(p) => platform = Some(p),
+                Err(e) => fail(&e),
+            },
+            "--quirk" => {
+                let value = value();
+                let (name, enabled) = match value.split_once('=') {
+                    Some((name, "on")) => (name.to_string(), true),
+                    Some((name, "off")) => (name.to_string(), false),
+                    _ => usage(),
+                };
+                quirk_overrides.push((name, enabled));
+            }
+            "--ipf" => match value().parse::<u32>() {
+                Ok(n) if n > 0 => instructions_per_frame = n,
+                _ => usage(),
+            },
+            "--hz" => match value().parse::<u32>() {
+                Ok(hz) if hz > 0 => instructions_per_frame = ((hz + 30) / 60).max(1),
+                _ => usage(),
+            },
+            "--vip-timing" => vip_timing = true,
+            "--stack-depth" => match value().parse::<usize>() {
+                Ok(depth) if (1..=MAX_STACK_DEPTH).contains(&depth) => stack_depth = Some(depth),
+                _ => usage(),
+            },
+            "--seed" => match value().parse::<u64>() {
+                Ok(s) => seed = Some(s),
+                Err(_) => usage(),
+            },
+            "--rng" => match value().as_str() {
+                "xorshift" => random = Some(Box::new(Xorshift::new(0))),
+                "vip" => random = Some(Box::new(VipRandom::new(0))),
+                name => fail(&format!("Unknown random number source: {}", name)),
+            },
+            "--debug" => debug = true,
+            "--load-state" => load_state = Some(value()),
+            "--save-state" => save_state = Some(value()),
+            "--record" => record = Some(value()),
+            "--replay" => replay = Some(value()),
+            "--rewind" => match value().parse::<u32>() {
+                Ok(seconds) => rewind_seconds = seconds,
+                Err(_) => usage(),
+            },
+            "--config" => config = Some(value()),
+            "--keys" => key_preset = Some(value()),
+            "--pause-on-focus-loss" => pause_on_focus_loss = true,
+            "--palette" => palette = Some(value()),
+            "--colors" => colors = Some(value().split(',').map(str::to_string).collect()),
+            "--scale" => match value().parse::<u32>() {
+                Ok(n) if n > 0 => scale = Some(n),
+                _ => usage(),
+            },
+            "--integer-scaling" => integer_scaling = true,
+            "--fullscreen" => fullscreen = true,
+            "--persistence" => persistence = Some(value()),
+            _ if arg.starts_with("--") || rom_file.is_some() => usage(),
+            _ => rom_file = Some(arg.clone()),
+        }
+    }
+    if load_state.is_some() && (record.is_some() || replay.is_some()) {
+        fail("movies start at the beginning of the ROM, --record and --replay cannot be combined with --load-state");
+    }
+    let mut quirks = platform.map(|p| p.quirks()).unwrap_or_default();
+    for (name, enabled) in quirk_overrides {
+        if let Err(e) = quirks.set(&name, enabled) {
+            fail(&e);
+        }
+    }
+    Options {
+        rom_file: rom_file.unwrap_or_else(|| usage()),
+        headless_frames,
+        platform,
+        quirks,
+        instructions_per_frame,
+        vip_timing,
+        stack_depth,
+        seed,
+        random,
+        debug,
+        load_state,
+        save_state,
+        record,
+        replay,
+        rewind_seconds,
+        config,
+        key_preset,
+        pause_on_focus_loss,
+        palette,
+        colors,
+        scale,
+        integer_scaling,
+        fullscreen,
+        persistence,
+    }
+}
+
+fn read_file(path: &str) -> Vec<u8> {
+    match fs::read(path) {
+        Ok(rom) => rom,
+        Err(e) => fail(&e.to_string()),
+    }
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("chip8 <Rom>");
-        process::exit(-1)
-    }
-    let rom_file = &args[1];
-    let mut rom_file = match fs::File::open(rom_file) {
-        Ok(rom_file) => rom_file,
-        Err(e) => {
-            eprintln!("{}", e);
-            process::exit(-1);
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("disasm") => {
+            match args.len() {
+                2 => match disasm::disassemble(&read_file(&args[1]), MEMORY_PROGRAM_BASE) {
+                    Ok(disassembly) => print!("{}", disassembly),
+                    Err(e) => fail(&e.to_string()),
+                },
+                _ => usage(),
+            }
+            return;
         }
+        Some("test") => run_tests(&args[1..]),
+        _ => {}
+    }
+    let mut options = parse_options(&args);
+    let rom = read_file(&options.rom_file);
+    let mut chip8 = match options.platform {
+        Some(platform) => Chip8::with_platform(platform),
+        None => Chip8::new(),
     };
-    let mut rom = vec![0_u8];
-    rom_file.read_to_end(&mut rom).expect("Load failed");
-    let rom_slice = rom.as_slice();
-    let mut cpu = CPU::new();
-    cpu.init(rom_slice);
-    cpu.start();
+    chip8.set_quirks(options.quirks);
+    chip8.set_instructions_per_frame(options.instructions_per_frame);
+    chip8.set_vip_timing(options.vip_timing);
+    if let Some(depth) = options.stack_depth {
+        chip8.set_stack_depth(depth);
+    }
+    if let Some(random) = options.random.take() {
+        chip8.set_random_source(random);
+    }
+    if let Some(seed) = options.seed {
+        chip8.set_seed(seed);
+    }
+    if let Err(e) = chip8.init(&rom) {
+        fail(&e.to_string());
+    }
+    if let Some(ref state_file) = options.load_state {
+        if let Err(e) = chip8.load_state(&read_file(state_file)) {
+            fail(&e.to_string());
+        }
+    }
+    match options.headless_frames {
+        Some(frames) => {
+            let mut frontend = Frontend::new(NoVideo, NoAudio, NoInput);
+            configure(&mut frontend, &mut chip8, &rom, &options);
+            let mut result = Ok(());
+            for _ in 0..frames {
+                result = frontend.run_frame(&mut chip8);
+                if result.is_err() || frontend.quit_requested() {
+                    break;
+                }
+            }
+            finish(&mut frontend, &chip8, &options);
+            if let Err(e) = result {
+                fail(&e.to_string());
+            }
+            print!("{}", chip8.frame_buffer());
+        }
+        None => {
+            let state_file = PathBuf::from(format!("{}.state", options.rom_file));
+            if let Err(e) = run_window(&mut chip8, &rom, &options, state_file) {
+                fail(&e.to_string());
+            }
+        }
+    }
+}
+
+/// Checks golden files, or rewrites their images with `--bless`, and exits with the result.
+fn run_tests(args: &[String]) -> ! {
+    let bless = args.first().map(String::as_str) == Some("--bless");
+    let mut files = Vec::new();
+    for arg in args.iter().skip(bless as usize) {
+        let path = Path::new(arg);
+        if path.is_dir() {
+            let entries = fs::read_dir(path).unwrap_or_else(|e| fail(&e.to_string()));
+            let mut goldens: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|e| e == "golden"))
+                .collect();
+            goldens.sort();
+            files.extend(goldens);
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+    if files.is_empty() {
+        usage();
+    }
+    let mut failed = 0;
+    for file in files.iter() {
+        let result = Golden::load(file).and_then(|golden| if bless {
+            golden.bless().and_then(|text| fs::write(file, text).map_err(|e| e.to_string()))
+        } else {
+            golden.check()
+        });
+        match result {
+            Ok(()) => println!("ok      {}", file.display()),
+            Err(e) => {
+                failed += 1;
+                println!("FAILED  {}\n{}", file.display(), e);
+            }
+        }
+    }
+    println!("{} passed, {} failed", files.len() - failed, failed);
+    process::exit(if failed == 0 { 0 } else { 1 })
+}
+
+/// Sets up the debugger and movies, which work the same with and without a window. `rom` is the
+/// ROM `chip8` was initialised with.
+fn configure<V: VideoSink, A: AudioSink, I: InputSource>(frontend: &mut Frontend<V, A, I>, chip8: &mut Chip8, rom: &[u8], options: &Options) {
+    if options.debug {
+        frontend.attach_debugger(Debugger::new());
+    }
+    if let Some(ref movie_file) = options.replay {
+        let movie = match Movie::parse(&read_file(movie_file)) {
+            Ok(movie) => movie,
+            Err(e) => fail(&e.to_string()),
+        };
+        chip8.set_seed(movie.header.seed);
+        if let Err(e) = movie.header.check(chip8, rom) {
+            fail(&format!("{}: {}", movie_file, e));
+        }
+        frontend.replay(MoviePlayer::new(movie.frames));
+    }
+    if let Some(ref movie_file) = options.record {
+        let recorder = fs::File::create(movie_file)
+            .and_then(|file| MovieRecorder::new(Box::new(BufWriter::new(file)), &MovieHeader::new(chip8, rom)));
+        match recorder {
+            Ok(recorder) => frontend.record(recorder),
+            Err(e) => fail(&e.to_string()),
+        }
+    }
+}
+
+/// The window settings: the global file or `--config`, overridden by the ROM's file and then by
+/// the command line.
+#[cfg(feature = "sdl")]
+fn load_config(options: &Options) -> Result<Config, String> {
+    let mut config = match options.config {
+        Some(ref path) => fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path, e))
+            .and_then(|text| Config::parse(&text).map_err(|e| format!("{}: {}", path, e)))?,
+        None => match config::global_path() {
+            Some(path) => Config::load(&path)?,
+            None => Config::default(),
+        },
+    };
+    config.merge(Config::load(&config::rom_path(&options.rom_file))?);
+    if let Some(ref preset) = options.key_preset {
+        config.keys.preset = Some(preset.clone());
+    }
+    if let Some(ref palette) = options.palette {
+        config.display.palette = Some(palette.clone());
+    }
+    if let Some(ref colors) = options.colors {
+        config.display.colors = Some(colors.clone());
+    }
+    if options.scale.is_some() {
+        config.display.scale = options.scale;
+    }
+    if options.integer_scaling {
+        config.display.integer_scaling = Some(true);
+    }
+    if options.fullscreen {
+        config.display.fullscreen = Some(true);
+    }
+    if let Some(ref persistence) = options.persistence {
+        config.display.persistence = Some(persistence.clone());
+    }
+    Ok(config)
+}
+
+/// Ends the movie being recorded and writes the save state, also after the program faulted.
+fn finish<V: VideoSink, A: AudioSink, I: InputSource>(frontend: &mut Frontend<V, A, I>, chip8: &Chip8, options: &Options) {
+    if let Err(e) = frontend.stop_recording() {
+        eprintln!("{}: {}", options.record.as_deref().unwrap_or_default(), e);
+    }
+    if let Some(ref state_file) = options.save_state {
+        if let Err(e) = fs::write(state_file, chip8.save_state()) {
+            eprintln!("{}: {}", state_file, e);
+        }
+    }
+}
+
+#[cfg(feature = "sdl")]
+fn run_window(chip8: &mut Chip8, rom: &[u8], options: &Options, state_file: PathBuf) -> Result<(), Chip8Error> {
+    let config = load_config(options).unwrap_or_else(|e| fail(&e));
+    let mut frontend = chip8::sdl::init(&config).unwrap_or_else(|e| fail(&e));
+    frontend.set_state_file(state_file);
+    if options.rewind_seconds > 0 {
+        frontend.enable_rewind(options.rewind_seconds);
+    }
+    frontend.set_pause_on_focus_loss(options.pause_on_focus_loss);
+    configure(&mut frontend, chip8, rom, options);
+    let result = frontend.start(chip8);
+    finish(&mut frontend, chip8, options);
+    result
+}
+
+#[cfg(not(feature = "sdl"))]
+fn run_window(_chip8: &mut Chip8, _rom: &[u8], _options: &Options, _state_file: PathBuf) -> Result<(), Chip8Error> {
+    fail("chip8 was built without the `sdl` feature, only --headless is available")
 }
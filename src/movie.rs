@@ -0,0 +1,252 @@
+//! Input movies: the hex key state of every frame of a session, replayed to reproduce it exactly.
+//!
+//! A movie starts with a header, all numbers little-endian:
+//!
+//! | Bytes | Content                                                              |
+//! |-------|----------------------------------------------------------------------|
+//! | 4     | the magic `C8MV`                                                     |
+//! | 2     | the format version                                                   |
+//! | 8     | the seed of the random number source used by CXNN                    |
+//! | 8     | the 64-bit FNV-1a hash of the ROM                                    |
+//! | 4     | the size of memory                                                   |
+//! | 2     | the quirks, bit N set if the Nth field of [`Quirks`] is enabled      |
+//! | 4     | the instructions per frame                                           |
+//! | 1     | 1 if instructions were timed like the COSMAC VIP, else 0             |
+//! | 1     | the call stack depth                                                 |
+//! | 1 + N | the length and name of the random number source, e.g. `xorshift`    |
+//!
+//! Then follows one `u16` per frame with bit N set if hex key N was held during that frame. There
+//! is no frame count, so a recording cut short by a crash is still a valid movie.
+//!
+//! Movies start at power-on. They replay in sync only with the ROM and the configuration they
+//! were recorded with, which [`MovieHeader::check`] compares before a replay. Version 1 movies
+//! stored only the seed and cannot be checked, so they are no longer read.
+
+use std::io::{self, Write};
+
+use crate::cpu::Chip8;
+use crate::error::Chip8Error;
+use crate::quirks::Quirks;
+
+/// Identifies a movie file.
+pub const MAGIC: &[u8; 4] = b"C8MV";
+/// The format version written by this build.
+pub const VERSION: u16 = 2;
+
+fn invalid(reason: &str) -> Chip8Error {
+    Chip8Error::InvalidMovie { reason: reason.to_string() }
+}
+
+fn key_mask(keys: &[bool; 16]) -> u16 {
+    keys.iter().enumerate().fold(0, |mask, (key, &pressed)| mask | (pressed as u16) << key)
+}
+
+/// The 64-bit FNV-1a hash movies identify their ROM by.
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    rom.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3))
+}
+
+/// The ROM and configuration of the core a movie was recorded with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovieHeader {
+    pub seed: u64,
+    pub rom_hash: u64,
+    pub memory_len: u32,
+    pub quirks: Quirks,
+    pub instructions_per_frame: u32,
+    pub vip_timing: bool,
+    pub stack_depth: u8,
+    pub random_source: String,
+}
+
+impl MovieHeader {
+    /// Describes `chip8` at power-on with `rom` loaded.
+    pub fn new(chip8: &Chip8, rom: &[u8]) -> Self {
+        MovieHeader {
+            seed: chip8.seed(),
+            rom_hash: rom_hash(rom),
+            memory_len: chip8.memory().len() as u32,
+            quirks: *chip8.quirks(),
+            instructions_per_frame: chip8.instructions_per_frame(),
+            vip_timing: chip8.vip_timing(),
+            stack_depth: chip8.stack_depth() as u8,
+            random_source: chip8.random_source_name().to_string(),
+        }
+    }
+
+    /// Checks that `chip8` with `rom` loaded replays the movie in sync. The seed is not compared,
+    /// a replay takes it from the movie. The error names the first difference.
+    pub fn check(&self, chip8: &Chip8, rom: &[u8]) -> Result<(), Chip8Error> {
+        let actual = MovieHeader { seed: self.seed, ..MovieHeader::new(chip8, rom) };
+        let mismatch = |what: String| Err(invalid(&format!("recorded with {}", what)));
+        if actual.rom_hash != self.rom_hash {
+            return mismatch("another ROM".to_string());
+        }
+        if actual.memory_len != self.memory_len {
+            return mismatch(format!("{} bytes of memory, replaying with {}, check --platform", self.memory_len, actual.memory_len));
+        }
+        for ((name, recorded), (_, enabled)) in self.quirks.iter().zip(actual.quirks.iter()) {
+            if recorded != enabled {
+                let on_off = |enabled| if enabled { "on" } else { "off" };
+                return mismatch(format!("quirk {}={}, replaying with {}", name, on_off(recorded), on_off(enabled)));
+            }
+        }
+        if actual.instructions_per_frame != self.instructions_per_frame {
+            return mismatch(format!("{} instructions per frame, replaying with {}", self.instructions_per_frame, actual.instructions_per_frame));
+        }
+        if actual.vip_timing != self.vip_timing {
+            return mismatch(format!("--vip-timing {}", if self.vip_timing { "on" } else { "off" }));
+        }
+        if actual.stack_depth != self.stack_depth {
+            return mismatch(format!("a stack depth of {}, replaying with {}", self.stack_depth, actual.stack_depth));
+        }
+        if actual.random_source != self.random_source {
+            return mismatch(format!("--rng {}, replaying with {}", self.random_source, actual.random_source));
+        }
+        Ok(())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let quirks = self.quirks.iter()
+            .enumerate()
+            .fold(0u16, |bits, (bit, (_, enabled))| bits | (enabled as u16) << bit);
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.rom_hash.to_le_bytes());
+        bytes.extend_from_slice(&self.memory_len.to_le_bytes());
+        bytes.extend_from_slice(&quirks.to_le_bytes());
+        bytes.extend_from_slice(&self.instructions_per_frame.to_le_bytes());
+        bytes.push(self.vip_timing as u8);
+        bytes.push(self.stack_depth);
+        bytes.push(self.random_source.len() as u8);
+        bytes.extend_from_slice(self.random_source.as_bytes());
+        bytes
+    }
+
+    /// Parses the header at the start of `bytes` and returns it with the frames that follow.
+    fn parse(bytes: &[u8]) -> Result<(MovieHeader, &[u8]), Chip8Error> {
+        if bytes.len() < 6 || &bytes[..4] != MAGIC {
+            return Err(invalid("not a movie"));
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != VERSION {
+            return Err(Chip8Error::InvalidMovie {
+                reason: format!("format version {} is not supported, only version {}", version, VERSION),
+            });
+        }
+        let mut rest = &bytes[6..];
+        let mut take = |n: usize| match rest.split_at_checked(n) {
+            Some((field, after)) => {
+                rest = after;
+                Ok(field)
+            }
+            None => Err(invalid("truncated header")),
+        };
+        let seed = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        let rom_hash = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        let memory_len = u32::from_le_bytes(take(4)?.try_into().unwrap());
+        let quirk_bits = u16::from_le_bytes(take(2)?.try_into().unwrap());
+        let instructions_per_frame = u32::from_le_bytes(take(4)?.try_into().unwrap());
+        let vip_timing = take(1)?[0] != 0;
+        let stack_depth = take(1)?[0];
+        let len = take(1)?[0] as usize;
+        let random_source = String::from_utf8(take(len)?.to_vec()).map_err(|_| invalid("invalid random number source"))?;
+        let mut quirks = Quirks::default();
+        let names: Vec<&str> = quirks.iter().map(|(name, _)| name).collect();
+        if quirk_bits >> names.len() != 0 {
+            return Err(invalid("unknown quirks"));
+        }
+        for (bit, name) in names.into_iter().enumerate() {
+            quirks.set(name, quirk_bits & (1 << bit) != 0).unwrap();
+        }
+        let header = MovieHeader {
+            seed,
+            rom_hash,
+            memory_len,
+            quirks,
+            instructions_per_frame,
+            vip_timing,
+            stack_depth,
+            random_source,
+        };
+        Ok((header, rest))
+    }
+}
+
+/// A recorded session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub header: MovieHeader,
+    /// The key mask of every frame, bit N for hex key N.
+    pub frames: Vec<u16>,
+}
+
+impl Movie {
+    pub fn parse(bytes: &[u8]) -> Result<Movie, Chip8Error> {
+        let (header, frames) = MovieHeader::parse(bytes)?;
+        let frames = frames.chunks_exact(2);
+        if !frames.remainder().is_empty() {
+            return Err(invalid("truncated frame"));
+        }
+        Ok(Movie {
+            header,
+            frames: frames.map(|frame| u16::from_le_bytes([frame[0], frame[1]])).collect(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header.to_bytes();
+        self.frames.iter().for_each(|frame| bytes.extend_from_slice(&frame.to_le_bytes()));
+        bytes
+    }
+}
+
+/// Writes a movie frame by frame as the session runs.
+pub struct MovieRecorder {
+    output: Box<dyn Write>,
+}
+
+impl MovieRecorder {
+    /// Starts a movie of a session run with the ROM and configuration in `header`.
+    pub fn new(mut output: Box<dyn Write>, header: &MovieHeader) -> io::Result<Self> {
+        output.write_all(&header.to_bytes())?;
+        Ok(MovieRecorder { output })
+    }
+
+    /// Appends a frame played with `keys` held.
+    pub fn record(&mut self, keys: &[bool; 16]) -> io::Result<()> {
+        self.output.write_all(&key_mask(keys).to_le_bytes())
+    }
+
+    /// Flushes the frames recorded so far and ends the movie.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+/// Plays back the key state of a movie, one frame at a time.
+pub struct MoviePlayer {
+    frames: Vec<u16>,
+    frame: usize,
+}
+
+impl MoviePlayer {
+    /// Plays the key masks `frames`, bit N for hex key N.
+    pub fn new(frames: Vec<u16>) -> Self {
+        MoviePlayer { frames, frame: 0 }
+    }
+
+    /// The keys of the next frame, or `None` once the movie has ended.
+    pub fn next_keys(&mut self) -> Option<[bool; 16]> {
+        let mask = *self.frames.get(self.frame)?;
+        self.frame += 1;
+        let mut keys = [false; 16];
+        keys.iter_mut().enumerate().for_each(|(key, pressed)| *pressed = mask & (1 << key) != 0);
+        Some(keys)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.frame >= self.frames.len()
+    }
+}
@@ -0,0 +1,32 @@
+/// A 16-bit CHIP-8 instruction, with accessors for its operand fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opcode(pub u16);
+
+impl Opcode {
+    /// The second nibble, a register index.
+    pub fn x(&self) -> usize {
+        ((self.0 & 0x0F00) >> 8) as usize
+    }
+    /// The third nibble, a register index.
+    pub fn y(&self) -> usize {
+        ((self.0 & 0x00F0) >> 4) as usize
+    }
+    /// The lowest nibble.
+    pub fn n(&self) -> u8 {
+        (self.0 & 0x000F) as u8
+    }
+    /// The lowest byte.
+    pub fn nn(&self) -> u8 {
+        (self.0 & 0x00FF) as u8
+    }
+    /// The lowest 12 bits, an address.
+    pub fn nnn(&self) -> u16 {
+        self.0 & 0x0FFF
+    }
+}
+
+impl From<u16> for Opcode {
+    fn from(opcode: u16) -> Self {
+        Opcode(opcode)
+    }
+}
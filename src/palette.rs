@@ -0,0 +1,89 @@
+//! The colours pixels are shown in.
+//!
+//! A pixel of the [`FrameBuffer`](crate::framebuffer::FrameBuffer) is the bitmask of the planes it
+//! is set in, and a palette has a colour for each of the four values: off, plane 1, plane 2 and
+//! both planes. Only XO-CHIP programs draw to plane 2, everything else uses the first two colours,
+//! the background and the foreground.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A colour, written as `#RRGGBB`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl FromStr for Rgb {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        match u32::from_str_radix(hex, 16) {
+            Ok(rgb) if hex.len() == 6 => Ok(Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)),
+            _ => Err(format!("Invalid colour: {}, expected #RRGGBB", s)),
+        }
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.0, self.1, self.2)
+    }
+}
+
+const PALETTES: &[(&str, [Rgb; 4])] = &[
+    ("default", [Rgb(0x00, 0x00, 0x00), Rgb(0xFE, 0x64, 0xFE), Rgb(0xFF, 0xA0, 0x00), Rgb(0xFF, 0xFF, 0xFF)]),
+    // P1 phosphor of early terminals and oscilloscopes
+    ("green", [Rgb(0x00, 0x10, 0x00), Rgb(0x33, 0xFF, 0x33), Rgb(0x1A, 0x80, 0x1A), Rgb(0xB3, 0xFF, 0xB3)]),
+    // P3 phosphor
+    ("amber", [Rgb(0x10, 0x08, 0x00), Rgb(0xFF, 0xB0, 0x00), Rgb(0xB0, 0x50, 0x00), Rgb(0xFF, 0xE0, 0xA0)]),
+    // the four shades of a green handheld LCD, dark pixels on a light background
+    ("lcd", [Rgb(0x9B, 0xBC, 0x0F), Rgb(0x0F, 0x38, 0x0F), Rgb(0x30, 0x62, 0x30), Rgb(0x8B, 0xAC, 0x0F)]),
+    ("high-contrast", [Rgb(0x00, 0x00, 0x00), Rgb(0xFF, 0xFF, 0xFF), Rgb(0xFF, 0xFF, 0x00), Rgb(0x00, 0xFF, 0xFF)]),
+    // the default colours of Octo, the XO-CHIP reference implementation
+    ("octo", [Rgb(0x99, 0x66, 0x00), Rgb(0xFF, 0xCC, 0x00), Rgb(0xFF, 0x66, 0x00), Rgb(0x66, 0x22, 0x00)]),
+];
+
+/// The colours of the four pixel values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: [Rgb; 4],
+}
+
+impl Palette {
+    /// A built-in palette, see [`Palette::names`].
+    pub fn named(name: &str) -> Option<Palette> {
+        PALETTES.iter()
+            .find(|(palette, _)| palette.eq_ignore_ascii_case(name))
+            .map(|&(_, colors)| Palette { colors })
+    }
+
+    /// The names of the built-in palettes.
+    pub fn names() -> impl Iterator<Item=&'static str> {
+        PALETTES.iter().map(|(name, _)| *name)
+    }
+
+    /// Replaces the first `colors.len()` colours: the background, the foreground and the XO-CHIP
+    /// colours of plane 2 and of both planes.
+    pub fn set_colors(&mut self, colors: &[Rgb]) -> Result<(), String> {
+        if colors.is_empty() || colors.len() > self.colors.len() {
+            return Err(format!("Expected 1 to {} colours, got {}", self.colors.len(), colors.len()));
+        }
+        self.colors[..colors.len()].copy_from_slice(colors);
+        Ok(())
+    }
+
+    /// The colour of a pixel of the framebuffer.
+    pub fn color(&self, pixel: u8) -> Rgb {
+        self.colors[pixel as usize & 0x03]
+    }
+
+    pub fn background(&self) -> Rgb {
+        self.colors[0]
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::named("default").unwrap()
+    }
+}
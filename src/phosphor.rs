@@ -0,0 +1,126 @@
+//! Rendering that hides the flicker of moving sprites.
+//!
+//! CHIP-8 programs move a sprite by XORing it away and drawing it again at its new position, so a
+//! sprite is often missing from the frame the screen shows. CRT phosphor glowed on for a moment
+//! after the beam had passed and hid that. [`Phosphor`] imitates it in one of two ways: a pixel is
+//! shown if it was set in any of the last N frames, or a pixel that goes off fades towards the
+//! background over a few frames.
+
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use crate::framebuffer::FrameBuffer;
+use crate::palette::{Palette, Rgb};
+
+/// The most frames [`Persistence::Frames`] combines.
+pub const MAX_PERSISTENCE_FRAMES: usize = 8;
+
+/// How long pixels stay visible after they go off, written as `off`, `frames:<N>` or
+/// `decay:<Strength>`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Persistence {
+    /// Every frame is shown as it is.
+    #[default]
+    Off,
+    /// Each pixel shows the planes it was set in during any of the last N frames, 1 to
+    /// [`MAX_PERSISTENCE_FRAMES`].
+    Frames(usize),
+    /// A pixel that goes off keeps this part of its distance to the background colour each frame,
+    /// from 0 (no persistence) to below 1.
+    Decay(f32),
+}
+
+impl FromStr for Persistence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid persistence: {}, expected off, frames:<1 to {}> or decay:<0 to below 1>", s, MAX_PERSISTENCE_FRAMES);
+        match s.split_once(':') {
+            None if s == "off" => Ok(Persistence::Off),
+            Some(("frames", n)) => match n.parse::<usize>() {
+                Ok(n) if (1..=MAX_PERSISTENCE_FRAMES).contains(&n) => Ok(Persistence::Frames(n)),
+                _ => Err(invalid()),
+            },
+            Some(("decay", strength)) => match strength.parse::<f32>() {
+                Ok(strength) if (0.0..1.0).contains(&strength) => Ok(Persistence::Decay(strength)),
+                _ => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Turns frames into the colours shown, remembering as much of the previous frames as the
+/// persistence needs. The history starts over when the resolution changes.
+pub struct Phosphor {
+    persistence: Persistence,
+    palette: Palette,
+    width: usize,
+    height: usize,
+    // the last frames, newest first, for Persistence::Frames
+    frames: VecDeque<Vec<u8>>,
+    // the colours shown last, for Persistence::Decay
+    glow: Vec<[f32; 3]>,
+    shown: Vec<Rgb>,
+}
+
+impl Phosphor {
+    pub fn new(persistence: Persistence, palette: Palette) -> Self {
+        Phosphor {
+            persistence,
+            palette,
+            width: 0,
+            height: 0,
+            frames: VecDeque::new(),
+            glow: Vec::new(),
+            shown: Vec::new(),
+        }
+    }
+
+    /// The colours of the pixels to show for `frame_buffer`, the next frame, row by row.
+    pub fn render(&mut self, frame_buffer: &FrameBuffer) -> &[Rgb] {
+        if (frame_buffer.width(), frame_buffer.height()) != (self.width, self.height) {
+            self.width = frame_buffer.width();
+            self.height = frame_buffer.height();
+            self.frames.clear();
+            self.glow.clear();
+        }
+        let pixels = frame_buffer.pixels();
+        self.shown.clear();
+        match self.persistence {
+            Persistence::Off => {
+                self.shown.extend(pixels.iter().map(|&pixel| self.palette.color(pixel)));
+            }
+            Persistence::Frames(n) => {
+                self.frames.truncate(n - 1);
+                self.frames.push_front(pixels.to_vec());
+                for position in 0..pixels.len() {
+                    let pixel = self.frames.iter().fold(0, |planes, frame| planes | frame[position]);
+                    self.shown.push(self.palette.color(pixel));
+                }
+            }
+            Persistence::Decay(strength) => {
+                if self.glow.is_empty() {
+                    self.glow = pixels.iter().map(|&pixel| channels(self.palette.color(pixel))).collect();
+                }
+                for (&pixel, glow) in pixels.iter().zip(self.glow.iter_mut()) {
+                    let target = channels(self.palette.color(pixel));
+                    for (channel, target) in glow.iter_mut().zip(target) {
+                        *channel = if pixel != 0 {
+                            target
+                        } else {
+                            target + (*channel - target) * strength
+                        };
+                    }
+                    let [r, g, b] = glow.map(|channel| channel.round() as u8);
+                    self.shown.push(Rgb(r, g, b));
+                }
+            }
+        }
+        &self.shown
+    }
+}
+
+fn channels(Rgb(r, g, b): Rgb) -> [f32; 3] {
+    [r as f32, g as f32, b as f32]
+}
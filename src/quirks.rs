@@ -0,0 +1,157 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Behaviours of ambiguous instructions that differ between CHIP-8 implementations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// 8XY6/8XYE shift VY and store the result in VX, instead of shifting VX in place.
+    pub shift_uses_vy: bool,
+    /// FX55/FX65 leave I set to I + X + 1, instead of leaving it unchanged.
+    pub load_store_increments_i: bool,
+    /// BNNN jumps to XNN + VX, instead of NNN + V0.
+    pub jump_uses_vx: bool,
+    /// DXYN clips sprites at the screen edges, instead of wrapping them to the opposite side.
+    pub clip_sprites: bool,
+    /// 8XY1/8XY2/8XY3 reset VF to 0.
+    pub vf_reset: bool,
+    /// DXYN waits for the next frame, so at most one sprite is drawn per frame.
+    pub display_wait: bool,
+    /// FX0A stores the key once it is released, instead of as soon as it is pressed.
+    pub key_wait_release: bool,
+}
+
+impl Quirks {
+    /// Enables or disables a quirk by its field name, e.g. `clip_sprites`.
+    pub fn set(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        let quirk = match name {
+            "shift_uses_vy" => &mut self.shift_uses_vy,
+            "load_store_increments_i" => &mut self.load_store_increments_i,
+            "jump_uses_vx" => &mut self.jump_uses_vx,
+            "clip_sprites" => &mut self.clip_sprites,
+            "vf_reset" => &mut self.vf_reset,
+            "display_wait" => &mut self.display_wait,
+            "key_wait_release" => &mut self.key_wait_release,
+            _ => return Err(format!("Unknown quirk: {}", name)),
+        };
+        *quirk = enabled;
+        Ok(())
+    }
+
+    /// Every quirk by its field name and whether it is enabled, in the order of the fields.
+    pub fn iter(&self) -> impl Iterator<Item=(&'static str, bool)> {
+        [
+            ("shift_uses_vy", self.shift_uses_vy),
+            ("load_store_increments_i", self.load_store_increments_i),
+            ("jump_uses_vx", self.jump_uses_vx),
+            ("clip_sprites", self.clip_sprites),
+            ("vf_reset", self.vf_reset),
+            ("display_wait", self.display_wait),
+            ("key_wait_release", self.key_wait_release),
+        ].into_iter()
+    }
+}
+
+/// The behaviour this interpreter had before quirks were configurable, except that FX0A waits for
+/// the key to be released as on all of the original platforms.
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            clip_sprites: false,
+            vf_reset: false,
+            display_wait: false,
+            key_wait_release: true,
+        }
+    }
+}
+
+/// Well-known CHIP-8 implementations, each with its own set of quirks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    /// The original interpreter on the COSMAC VIP.
+    Vip,
+    /// CHIP-48 on the HP-48 calculators.
+    Chip48,
+    /// SUPER-CHIP 1.1 on the HP-48 calculators.
+    SuperChip,
+    /// XO-CHIP, as implemented by Octo.
+    XoChip,
+}
+
+impl Platform {
+    /// The size of the address space in bytes.
+    pub fn memory_len(&self) -> usize {
+        match self {
+            Platform::XoChip => 0x10000,
+            _ => 0x1000,
+        }
+    }
+
+    /// The number of return addresses the call stack has room for.
+    pub fn stack_depth(&self) -> usize {
+        match self {
+            Platform::Vip => 12,
+            _ => 16,
+        }
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            Platform::Vip => Quirks {
+                shift_uses_vy: true,
+                load_store_increments_i: true,
+                jump_uses_vx: false,
+                clip_sprites: true,
+                vf_reset: true,
+                display_wait: true,
+                key_wait_release: true,
+            },
+            Platform::Chip48 | Platform::SuperChip => Quirks {
+                shift_uses_vy: false,
+                load_store_increments_i: false,
+                jump_uses_vx: true,
+                clip_sprites: true,
+                vf_reset: false,
+                display_wait: false,
+                key_wait_release: true,
+            },
+            Platform::XoChip => Quirks {
+                shift_uses_vy: true,
+                load_store_increments_i: true,
+                jump_uses_vx: false,
+                clip_sprites: false,
+                vf_reset: false,
+                display_wait: false,
+                key_wait_release: true,
+            },
+        }
+    }
+}
+
+impl FromStr for Platform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vip" | "chip8" => Ok(Platform::Vip),
+            "chip48" => Ok(Platform::Chip48),
+            "schip" | "superchip" => Ok(Platform::SuperChip),
+            "xochip" => Ok(Platform::XoChip),
+            _ => Err(format!("Unknown platform: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Platform::Vip => "vip",
+            Platform::Chip48 => "chip48",
+            Platform::SuperChip => "schip",
+            Platform::XoChip => "xochip",
+        };
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,147 @@
+//! Random number sources for CXNN.
+//!
+//! The core owns a boxed [`RandomSource`], seeded from [`Chip8::set_seed`](crate::cpu::Chip8::set_seed)
+//! or randomly at start-up. Both sources here are fully determined by their seed and small enough
+//! to be stored in save states.
+
+/// Produces the bytes CXNN masks with NN.
+pub trait RandomSource {
+    /// A short name identifying the algorithm, stored in save states.
+    fn name(&self) -> &'static str;
+
+    /// Restarts the sequence from `seed`.
+    fn reseed(&mut self, seed: u64);
+
+    /// The next random byte.
+    fn next_byte(&mut self) -> u8;
+
+    /// Called once per frame, when the timers tick.
+    fn frame(&mut self) {}
+
+    /// The internal state, for save states.
+    fn state(&self) -> Vec<u8>;
+
+    /// Restores a state returned by [`RandomSource::state`]. Returns false if it is invalid.
+    fn set_state(&mut self, state: &[u8]) -> bool;
+}
+
+/// The xorshift64* generator, the default source.
+pub struct Xorshift {
+    state: u64,
+}
+
+impl Xorshift {
+    pub fn new(seed: u64) -> Self {
+        let mut xorshift = Xorshift { state: 0 };
+        xorshift.reseed(seed);
+        xorshift
+    }
+}
+
+impl RandomSource for Xorshift {
+    fn name(&self) -> &'static str {
+        "xorshift"
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        // the all-zero state is a fixed point
+        self.state = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+
+    fn state(&self) -> Vec<u8> {
+        self.state.to_le_bytes().to_vec()
+    }
+
+    fn set_state(&mut self, state: &[u8]) -> bool {
+        match <[u8; 8]>::try_from(state) {
+            Ok(bytes) if bytes != [0; 8] => {
+                self.state = u64::from_le_bytes(bytes);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Page 0x100 of the COSMAC VIP CHIP-8 interpreter, which [`VipRandom`] reads from. The
+/// interpreter occupies 0x000-0x1FF of VIP memory; this page holds the handlers of most
+/// instructions and ends with the `00E0 004B` the interpreter starts programs with at 0x1FC.
+const VIP_INTERPRETER_PAGE: [u8; 256] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x45, 0xA3, 0x98, 0x56, 0xD4, 0xF8, 0x81, 0xBC, 0xF8, 0x95, 0xAC,
+    0x22, 0xDC, 0x12, 0x56, 0xD4, 0x06, 0xB8, 0xD4, 0x06, 0xA8, 0xD4, 0x64, 0x0A, 0x01, 0xE6, 0x8A,
+    0xF4, 0xAA, 0x3B, 0x28, 0x9A, 0xFC, 0x01, 0xBA, 0xD4, 0xF8, 0x81, 0xBA, 0x06, 0xFA, 0x0F, 0xAA,
+    0x0A, 0xAA, 0xD4, 0xE6, 0x06, 0xBF, 0x93, 0xBE, 0xF8, 0x1B, 0xAE, 0x2A, 0x1A, 0xF8, 0x00, 0x5A,
+    0x0E, 0xF5, 0x3B, 0x4B, 0x56, 0x0A, 0xFC, 0x01, 0x5A, 0x30, 0x40, 0x4E, 0xF6, 0x3B, 0x3C, 0x9F,
+    0x56, 0x2A, 0x2A, 0xD4, 0x00, 0x22, 0x86, 0x52, 0xF8, 0xF0, 0xA7, 0x07, 0x5A, 0x87, 0xF3, 0x17,
+    0x1A, 0x3A, 0x5B, 0x12, 0xD4, 0x22, 0x86, 0x52, 0xF8, 0xF0, 0xA7, 0x0A, 0x57, 0x87, 0xF3, 0x17,
+    0x1A, 0x3A, 0x6B, 0x12, 0xD4, 0x15, 0x85, 0x22, 0x73, 0x95, 0x52, 0x25, 0x45, 0xA5, 0x86, 0xFA,
+    0x0F, 0xB5, 0xD4, 0x45, 0xE6, 0xF3, 0x3A, 0x82, 0x15, 0x15, 0xD4, 0x45, 0xE6, 0xF3, 0x3A, 0x88,
+    0xD4, 0x45, 0x07, 0x30, 0x8C, 0x45, 0x07, 0x30, 0x84, 0xE6, 0x62, 0x26, 0x45, 0xA3, 0x36, 0x88,
+    0xD4, 0x3E, 0x88, 0xD4, 0xF8, 0xF0, 0xA7, 0xE7, 0x45, 0xF4, 0xA5, 0x86, 0xFA, 0x0F, 0x3B, 0xB2,
+    0xFC, 0x01, 0xB5, 0xD4, 0x45, 0x56, 0xD4, 0x45, 0xE6, 0xF4, 0x56, 0xD4, 0x45, 0xFA, 0x0F, 0x3A,
+    0xC4, 0x07, 0x56, 0xD4, 0xAF, 0x22, 0xF8, 0xD3, 0x73, 0x8F, 0xF9, 0xF0, 0x52, 0xE6, 0x07, 0xD2,
+    0x56, 0xF8, 0xFF, 0xA6, 0xF8, 0x00, 0x7E, 0x56, 0xD4, 0x19, 0x89, 0xAE, 0x93, 0xBE, 0x99, 0xEE,
+    0xF4, 0x56, 0x76, 0xE6, 0xF4, 0xB9, 0x56, 0x45, 0xF2, 0x56, 0xD4, 0x45, 0xAA, 0x86, 0xFA, 0x0F,
+    0xBA, 0xD4, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xE0, 0x00, 0x4B,
+];
+
+/// The pseudo-random routine of the COSMAC VIP interpreter, the CXNN handler at 0x1D9.
+///
+/// It increments the 16-bit register R9 and adds the high byte of R9 to the interpreter byte at
+/// 0x100 plus the low byte of R9. The sum is added to itself shifted right with the carry shifted
+/// in; that result is the random byte and the new high byte of R9. The VIP's interrupt routine
+/// also increments R9 once per frame, so the sequence depends on when CXNN runs. R9 is not
+/// initialised at power-on; the seed stands in for its value.
+pub struct VipRandom {
+    r9: u16,
+}
+
+impl VipRandom {
+    pub fn new(seed: u64) -> Self {
+        VipRandom { r9: seed as u16 }
+    }
+}
+
+impl RandomSource for VipRandom {
+    fn name(&self) -> &'static str {
+        "vip"
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.r9 = seed as u16;
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        self.r9 = self.r9.wrapping_add(1);
+        let [high, low] = self.r9.to_be_bytes();
+        let (sum, carry) = high.overflowing_add(VIP_INTERPRETER_PAGE[low as usize]);
+        let result = sum.wrapping_add(sum >> 1 | (carry as u8) << 7);
+        self.r9 = u16::from_be_bytes([result, low]);
+        result
+    }
+
+    fn frame(&mut self) {
+        self.r9 = self.r9.wrapping_add(1);
+    }
+
+    fn state(&self) -> Vec<u8> {
+        self.r9.to_le_bytes().to_vec()
+    }
+
+    fn set_state(&mut self, state: &[u8]) -> bool {
+        match <[u8; 2]>::try_from(state) {
+            Ok(bytes) => {
+                self.r9 = u16::from_le_bytes(bytes);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! A bounded history of save states for stepping gameplay backwards.
+//!
+//! Only the newest state is kept whole. Every older state is stored as the difference to the state
+//! after it: the two are XORed, which leaves zeros wherever memory, screen and registers did not
+//! change, and the runs of zeros are run-length encoded. A frame of a typical game costs a few
+//! dozen bytes instead of a full copy of memory.
+
+use std::collections::VecDeque;
+
+/// An older state, stored relative to the state after it.
+struct Delta {
+    len: usize,
+    encoded: Vec<u8>,
+}
+
+/// A ring buffer of save states, one per frame, holding at most `capacity` of them.
+pub struct Rewind {
+    capacity: usize,
+    latest: Option<Vec<u8>>,
+    // oldest first, each entry turns the state after it back into the one before
+    deltas: VecDeque<Delta>,
+}
+
+impl Rewind {
+    pub fn new(capacity: usize) -> Self {
+        Rewind {
+            capacity: capacity.max(1),
+            latest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    /// The number of states in the history.
+    pub fn len(&self) -> usize {
+        self.deltas.len() + self.latest.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    /// Records `state` as the newest state, dropping the oldest one if the history is full.
+    pub fn push(&mut self, state: Vec<u8>) {
+        if let Some(previous) = self.latest.take() {
+            self.deltas.push_back(Delta {
+                len: previous.len(),
+                encoded: encode(&xor(&previous, &state)),
+            });
+            if self.deltas.len() >= self.capacity {
+                self.deltas.pop_front();
+            }
+        }
+        self.latest = Some(state);
+    }
+
+    /// Drops the newest state and returns the one before it, which becomes the newest.
+    /// Returns `None` once only the oldest state is left.
+    pub fn step_back(&mut self) -> Option<&[u8]> {
+        let delta = self.deltas.pop_back()?;
+        let latest = self.latest.as_mut()?;
+        let mut previous = xor(latest, &decode(&delta.encoded));
+        previous.truncate(delta.len);
+        *latest = previous;
+        self.latest.as_deref()
+    }
+}
+
+/// XORs `a` and `b`, treating the shorter one as padded with zeros.
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut result = long.to_vec();
+    result.iter_mut().zip(short).for_each(|(r, s)| *r ^= s);
+    result
+}
+
+/// Encodes `bytes` as a sequence of (zero count, literal count, literals), counts as u16 LE.
+fn encode(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let zeros = bytes[i..].iter().take(u16::MAX as usize).take_while(|&&b| b == 0).count();
+        i += zeros;
+        let literals = bytes[i..].iter().take(u16::MAX as usize).take_while(|&&b| b != 0).count();
+        encoded.extend_from_slice(&(zeros as u16).to_le_bytes());
+        encoded.extend_from_slice(&(literals as u16).to_le_bytes());
+        encoded.extend_from_slice(&bytes[i..i + literals]);
+        i += literals;
+    }
+    encoded
+}
+
+fn decode(encoded: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut i = 0;
+    while i + 4 <= encoded.len() {
+        let zeros = u16::from_le_bytes([encoded[i], encoded[i + 1]]) as usize;
+        let literals = u16::from_le_bytes([encoded[i + 2], encoded[i + 3]]) as usize;
+        i += 4;
+        bytes.resize(bytes.len() + zeros, 0);
+        bytes.extend_from_slice(&encoded[i..i + literals]);
+        i += literals;
+    }
+    bytes
+}
@@ -0,0 +1,122 @@
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, TextureCreator};
+use sdl2::Sdl;
+use sdl2::surface::Surface;
+use sdl2::video::{FullscreenType, Window, WindowContext};
+
+use crate::framebuffer::{FrameBuffer, LORES_HEIGHT, LORES_WIDTH};
+use crate::frontend::VideoSink;
+use crate::palette::{Palette, Rgb};
+use crate::phosphor::{Persistence, Phosphor};
+
+/// How the window shows the screen.
+#[derive(Debug, Clone, Copy)]
+pub struct WindowOptions {
+    /// The initial window size in window pixels per pixel of the 64x32 screen.
+    pub scale: u32,
+    /// Scale the screen by whole numbers only, so that every pixel is the same size.
+    pub integer_scaling: bool,
+    pub fullscreen: bool,
+    pub persistence: Persistence,
+}
+
+pub struct Display {
+    phosphor: Phosphor,
+    integer_scaling: bool,
+    frame_buffer: Vec<u8>,
+    renderer: Canvas<Window>,
+    texture_creator: TextureCreator<WindowContext>,
+}
+
+impl Display {
+    /// Opens the resizable window, showing pixels in the colours of `palette`.
+    pub fn new(sdl_ctx: &Sdl, palette: &Palette, options: WindowOptions) -> Self {
+        let video_subsystem = sdl_ctx.video().unwrap();
+        let (width, height) = (LORES_WIDTH as u32, LORES_HEIGHT as u32);
+        let mut window = video_subsystem.window("chip-8", width * options.scale, height * options.scale)
+            .position_centered()
+            .resizable()
+            .build().unwrap();
+        window.set_minimum_size(width, height).unwrap();
+        if options.fullscreen {
+            window.set_fullscreen(FullscreenType::Desktop).unwrap();
+        }
+        let mut renderer = window.into_canvas().accelerated().build().unwrap();
+        let texture_creator = renderer.texture_creator();
+        // the bars around the screen when the window has another aspect ratio
+        let Rgb(r, g, b) = palette.background();
+        renderer.set_draw_color(Color::RGB(r, g, b));
+        renderer.clear();
+        Display {
+            phosphor: Phosphor::new(options.persistence, *palette),
+            integer_scaling: options.integer_scaling,
+            frame_buffer: Vec::new(),
+            renderer,
+            texture_creator,
+        }
+    }
+
+    fn render(&mut self, pixels: &FrameBuffer) {
+        let (width, height) = (pixels.width(), pixels.height());
+        self.frame_buffer.resize(width * height * 4, 0);
+        for (position, &Rgb(r, g, b)) in self.phosphor.render(pixels).iter().enumerate() {
+            let position = position * 4; // Since Each pixel occupy 4 byte in vec
+            // ARGB8888 is stored as B, G, R, A in little-endian memory
+            self.frame_buffer[position..position + 4].copy_from_slice(&u32::from_be_bytes([0xFF, r, g, b]).to_ne_bytes());
+        }
+        self.renderer.clear();
+        let surface = Surface::from_data(
+            self.frame_buffer.as_mut(),
+            width as u32, height as u32,
+            width as u32 * 4,
+            PixelFormatEnum::ARGB8888,
+        ).unwrap();
+        // the texture is stretched over the viewport, whatever the resolution
+        let texture = self.texture_creator
+            .create_texture_from_surface(surface)
+            .unwrap();
+        let output = self.renderer.output_size().unwrap();
+        let viewport = viewport(output, (width as u32, height as u32), self.integer_scaling);
+        self.renderer.copy(&texture, None, viewport).unwrap();
+        self.renderer.present();
+    }
+}
+
+impl VideoSink for Display {
+    fn present(&mut self, frame_buffer: &FrameBuffer) {
+        self.render(frame_buffer);
+    }
+
+    fn toggle_fullscreen(&mut self) {
+        let window = self.renderer.window_mut();
+        let fullscreen = match window.fullscreen_state() {
+            FullscreenType::Off => FullscreenType::Desktop,
+            _ => FullscreenType::Off,
+        };
+        if let Err(e) = window.set_fullscreen(fullscreen) {
+            eprintln!("Cannot switch fullscreen: {}", e);
+        }
+    }
+}
+
+/// The largest rectangle in the middle of `output` with the aspect ratio of `frame`, or `None`
+/// if the output is empty. With `integer_scaling` its sides are a whole multiple of the frame's,
+/// unless the output is smaller than the frame.
+fn viewport(output: (u32, u32), frame: (u32, u32), integer_scaling: bool) -> Option<Rect> {
+    let ((output_width, output_height), (frame_width, frame_height)) = (output, frame);
+    let scale = (output_width / frame_width).min(output_height / frame_height);
+    let (width, height) = if integer_scaling && scale > 0 {
+        (frame_width * scale, frame_height * scale)
+    } else if output_width * frame_height > output_height * frame_width {
+        (output_height * frame_width / frame_height, output_height)
+    } else {
+        (output_width, output_width * frame_height / frame_width)
+    };
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let x = (output_width - width) / 2;
+    let y = (output_height - height) / 2;
+    Some(Rect::new(x as i32, y as i32, width, height))
+}
@@ -0,0 +1,113 @@
+use std::collections::{HashMap, HashSet};
+
+use sdl2::GameControllerSubsystem;
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+
+use crate::keymap::Keymap;
+
+/// Game controllers, opened as they are plugged in, and the hex keys their buttons, sticks and
+/// triggers are bound to. Every controller drives the same hex keys.
+pub struct Gamepads {
+    subsystem: GameControllerSubsystem,
+    // by joystick instance id
+    controllers: HashMap<u32, GameController>,
+    buttons: HashMap<Button, u8>,
+    // the hex keys of axes pushed towards their negative (false) or positive (true) end
+    axes: HashMap<(Axis, bool), u8>,
+    threshold: i16,
+    held_buttons: HashSet<(u32, Button)>,
+    // hex keys whose buttons were pressed since the last frame, so a tap between frames counts
+    latched_keys: [bool; 16],
+    axis_values: HashMap<(u32, Axis), i16>,
+}
+
+impl Gamepads {
+    /// Reads the inputs bound in `keymap`. An axis presses its hex key once it is pushed
+    /// `threshold` of the way, from 0 to 1. Fails if SDL does not know the name of an input.
+    pub fn new(subsystem: GameControllerSubsystem, keymap: &Keymap, threshold: f32) -> Result<Self, String> {
+        let mut buttons = HashMap::new();
+        let mut axes = HashMap::new();
+        for (name, hex) in keymap.bindings() {
+            if let Some(button) = Button::from_string(name) {
+                buttons.insert(button, hex);
+                continue;
+            }
+            let axis = name.strip_suffix('+').map(|axis| (axis, true))
+                .or_else(|| name.strip_suffix('-').map(|axis| (axis, false)))
+                .and_then(|(axis, positive)| Axis::from_string(axis).map(|axis| (axis, positive)));
+            match axis {
+                Some(axis) => axes.insert(axis, hex),
+                None => return Err(format!("Unknown controller input: {}", name)),
+            };
+        }
+        Ok(Gamepads {
+            subsystem,
+            controllers: HashMap::new(),
+            buttons,
+            axes,
+            threshold: (threshold * i16::MAX as f32) as i16,
+            held_buttons: HashSet::new(),
+            latched_keys: [false; 16],
+            axis_values: HashMap::new(),
+        })
+    }
+
+    /// Handles controller events. SDL reports the controllers connected at start-up as added too.
+    pub fn on_controller_event(&mut self, event: &Event) {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => match self.subsystem.open(which) {
+                Ok(controller) => {
+                    eprintln!("Controller connected: {}", controller.name());
+                    self.controllers.insert(controller.instance_id(), controller);
+                }
+                Err(e) => eprintln!("Cannot open controller {}: {}", which, e),
+            },
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if let Some(controller) = self.controllers.remove(&which) {
+                    eprintln!("Controller disconnected: {}", controller.name());
+                }
+                self.held_buttons.retain(|&(id, _)| id != which);
+                self.axis_values.retain(|&(id, _), _| id != which);
+            }
+            Event::ControllerButtonDown { which, button, .. } => {
+                self.held_buttons.insert((which, button));
+                if let Some(&hex) = self.buttons.get(&button) {
+                    self.latched_keys[hex as usize] = true;
+                }
+            }
+            Event::ControllerButtonUp { which, button, .. } => {
+                self.held_buttons.remove(&(which, button));
+            }
+            Event::ControllerAxisMotion { which, axis, value, .. } => {
+                self.axis_values.insert((which, axis), value);
+            }
+            _ => {}
+        }
+    }
+
+    /// Presses the hex keys of the held buttons, of the buttons pressed since the last call and of
+    /// the axes pushed past the threshold.
+    pub fn press_keys(&mut self, keys: &mut [bool; 16]) {
+        for (key, latched) in keys.iter_mut().zip(self.latched_keys.iter_mut()) {
+            *key |= std::mem::take(latched);
+        }
+        for (_, button) in self.held_buttons.iter() {
+            if let Some(&hex) = self.buttons.get(button) {
+                keys[hex as usize] = true;
+            }
+        }
+        for (&(_, axis), &value) in self.axis_values.iter() {
+            let direction = if value >= self.threshold {
+                Some(true)
+            } else if value <= -self.threshold {
+                Some(false)
+            } else {
+                None
+            };
+            if let Some(&hex) = direction.and_then(|positive| self.axes.get(&(axis, positive))) {
+                keys[hex as usize] = true;
+            }
+        }
+    }
+}
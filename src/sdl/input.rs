@@ -0,0 +1,52 @@
+use sdl2::event::{Event, WindowEvent};
+use sdl2::EventPump;
+
+use crate::frontend::{Command, InputSource};
+use crate::sdl::gamepad::Gamepads;
+use crate::sdl::keyboard::Keyboard;
+
+/// Reads the SDL event queue and combines the keyboard and the game controllers. A hex key is
+/// pressed if any of them presses it. A key pressed and released within one frame is still pressed
+/// for that frame and released in the next.
+pub struct Input {
+    event_pump: EventPump,
+    keyboard: Keyboard,
+    gamepads: Gamepads,
+    // window events
+    commands: Vec<Command>,
+}
+
+impl Input {
+    pub fn new(event_pump: EventPump, keyboard: Keyboard, gamepads: Gamepads) -> Self {
+        Input { event_pump, keyboard, gamepads, commands: Vec::new() }
+    }
+}
+
+impl InputSource for Input {
+    fn poll(&mut self, keys: &mut [bool; 16]) {
+        for ref event in self.event_pump.poll_iter() {
+            match event {
+                Event::KeyDown { .. } | Event::KeyUp { .. } => {
+                    self.keyboard.on_keyboard_event(event);
+                }
+                Event::ControllerDeviceAdded { .. } | Event::ControllerDeviceRemoved { .. }
+                | Event::ControllerButtonDown { .. } | Event::ControllerButtonUp { .. }
+                | Event::ControllerAxisMotion { .. } => {
+                    self.gamepads.on_controller_event(event);
+                }
+                Event::Quit { .. } => self.commands.push(Command::Quit),
+                Event::Window { win_event: WindowEvent::FocusLost, .. } => self.commands.push(Command::FocusLost),
+                Event::Window { win_event: WindowEvent::FocusGained, .. } => self.commands.push(Command::FocusGained),
+                _ => {}
+            }
+        }
+        *keys = self.keyboard.pressed_keys();
+        self.gamepads.press_keys(keys);
+    }
+
+    fn commands(&mut self) -> Vec<Command> {
+        let mut commands = std::mem::take(&mut self.commands);
+        commands.extend(self.keyboard.commands());
+        commands
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+
+use crate::frontend::Command;
+use crate::keymap::Keymap;
+
+/// The hex keys and commands of the host keyboard.
+pub struct Keyboard {
+    hex_keys: HashMap<Keycode, u8>,
+    // the bound host keys held down, several of which may press the same hex key
+    held_keys: HashSet<Keycode>,
+    // keys pressed since the last frame, so a key released again before it is seen still counts
+    latched_key: [bool; 16],
+    commands: Vec<Command>,
+    rewind_held: bool,
+}
+
+impl Keyboard {
+    /// Reads the keys bound in `keymap`. Fails if SDL does not know the name of a host key.
+    pub fn new(keymap: &Keymap) -> Result<Self, String> {
+        let hex_keys = keymap.bindings()
+            .map(|(name, hex)| match Keycode::from_name(name) {
+                Some(keycode) => Ok((keycode, hex)),
+                None => Err(format!("Unknown key: {}", name)),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Keyboard {
+            hex_keys,
+            held_keys: HashSet::new(),
+            latched_key: [false; 16],
+            commands: Vec::new(),
+            rewind_held: false,
+        })
+    }
+
+    fn keycode_to_hex(&self, keycode: &Keycode) -> Option<u8> {
+        self.hex_keys.get(keycode).copied()
+    }
+
+    fn keycode_to_command(keycode: &Keycode) -> Option<Command> {
+        match keycode {
+            Keycode::F5 => Some(Command::SaveState),
+            Keycode::F9 => Some(Command::LoadState),
+            Keycode::Equals => Some(Command::SpeedUp),
+            Keycode::Minus => Some(Command::SpeedDown),
+            Keycode::Escape => Some(Command::Quit),
+            Keycode::F11 => Some(Command::ToggleFullscreen),
+            _ => None,
+        }
+    }
+
+    pub fn on_keyboard_event(&mut self, event: &Event) {
+        match event {
+            Event::KeyDown { keycode: Some(ref key), repeat, .. } => {
+                #[cfg(feature = "log_debug")]
+                println!("KeyDown => {}", key);
+                if let Some(hex) = self.keycode_to_hex(key) {
+                    self.held_keys.insert(*key);
+                    self.latched_key[hex as usize] = true;
+                } else if *key == Keycode::Backspace {
+                    self.rewind_held = true;
+                } else if let Some(command) = Keyboard::keycode_to_command(key).filter(|_| !repeat) {
+                    self.commands.push(command);
+                }
+            }
+            Event::KeyUp { keycode: Some(ref key), .. } => {
+                #[cfg(feature = "log_debug")]
+                println!("KeyUp => {}", key);
+                if self.keycode_to_hex(key).is_some() {
+                    self.held_keys.remove(key);
+                } else if *key == Keycode::Backspace {
+                    self.rewind_held = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The hex keys held now by any of their host keys, or pressed since the last call. A key
+    /// pressed and released between two calls is reported once and released by the next call.
+    pub fn pressed_keys(&mut self) -> [bool; 16] {
+        let mut keys = [false; 16];
+        for key in self.held_keys.iter() {
+            keys[self.hex_keys[key] as usize] = true;
+        }
+        for (key, latched) in keys.iter_mut().zip(self.latched_key.iter_mut()) {
+            *key |= std::mem::take(latched);
+        }
+        keys
+    }
+
+    /// The commands since the last call, and rewinding while Backspace is held.
+    pub fn commands(&mut self) -> Vec<Command> {
+        if self.rewind_held {
+            self.commands.push(Command::Rewind);
+        }
+        std::mem::take(&mut self.commands)
+    }
+}
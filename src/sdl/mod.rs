@@ -0,0 +1,37 @@
+pub mod display;
+pub mod gamepad;
+pub mod input;
+pub mod keyboard;
+pub mod sound;
+
+use crate::config::Config;
+use crate::frontend::Frontend;
+use crate::sdl::display::{Display, WindowOptions};
+use crate::sdl::gamepad::Gamepads;
+use crate::sdl::input::Input;
+use crate::sdl::keyboard::Keyboard;
+use crate::sdl::sound::Sound;
+
+pub type SdlFrontend = Frontend<Display, Sound, Input>;
+
+/// Opens the window, audio device and game controllers and wires them up as a [`Frontend`], with
+/// the palette, window settings and the bindings of the keyboard and controllers of `config`.
+pub fn init(config: &Config) -> Result<SdlFrontend, String> {
+    let sdl_context = sdl2::init()?;
+    let keyboard = Keyboard::new(&config.keymap()?)?;
+    let gamepad_keymap = config.gamepad_keymap()?;
+    let gamepads = Gamepads::new(sdl_context.game_controller()?, &gamepad_keymap, config.stick_threshold()?)?;
+    let input = Input::new(sdl_context.event_pump()?, keyboard, gamepads);
+    let palette = config.palette()?;
+    let window = WindowOptions {
+        scale: config.scale()?,
+        integer_scaling: config.display.integer_scaling.unwrap_or(false),
+        fullscreen: config.display.fullscreen.unwrap_or(false),
+        persistence: config.persistence()?,
+    };
+    Ok(Frontend::new(
+        Display::new(&sdl_context, &palette, window),
+        Sound::new(&sdl_context),
+        input,
+    ))
+}
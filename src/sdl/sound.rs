@@ -0,0 +1,98 @@
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::Sdl;
+
+use crate::frontend::AudioSink;
+
+pub struct Sound {
+    device: AudioDevice<SquareWave>,
+}
+
+struct SquareWave {
+    freq: f32,
+    phase_inc: f32,
+    phase: f32,
+    volume: f32,
+    // XO-CHIP pattern, 128 one-bit samples looped instead of the square wave
+    pattern: Option<[u8; 16]>,
+    pattern_phase_inc: f32,
+}
+
+impl AudioCallback for SquareWave {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        if let Some(pattern) = self.pattern {
+            // Play the pattern, the phase runs over all 128 bits
+            for x in out.iter_mut() {
+                let bit = (self.phase * 128.0) as usize % 128;
+                *x = if pattern[bit / 8] & (0x80 >> (bit % 8)) != 0 {
+                    self.volume
+                } else {
+                    -self.volume
+                };
+                self.phase = (self.phase + self.pattern_phase_inc) % 1.0;
+            }
+            return;
+        }
+        // Generate a square wave
+        for x in out.iter_mut() {
+            *x = if self.phase <= 0.5 {
+                self.volume
+            } else {
+                -self.volume
+            };
+            self.phase = (self.phase + self.phase_inc) % 1.0;
+        }
+    }
+}
+
+impl Sound {
+    pub fn new(sdl_ctx: &Sdl) -> Self {
+        let audio_subsystem = sdl_ctx.audio().unwrap();
+        let spec = AudioSpecDesired {
+            freq: Some(44100),
+            channels: Some(1),
+            samples: None,
+        };
+        let device = audio_subsystem.open_playback(
+            None,
+            &spec,
+            |spec| {
+                SquareWave {
+                    freq: spec.freq as f32,
+                    phase_inc: 440.0 / spec.freq as f32,
+                    phase: 0.0,
+                    volume: 0.25,
+                    pattern: None,
+                    pattern_phase_inc: 0.0,
+                }
+            },
+        )
+            .unwrap();
+        Sound { device }
+    }
+
+    pub fn pause(&self) {
+        self.device.pause()
+    }
+
+    pub fn resume(&self) {
+        self.device.resume()
+    }
+}
+
+impl AudioSink for Sound {
+    fn set_beep(&mut self, on: bool) {
+        if on {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    fn set_pattern(&mut self, pattern: &[u8; 16], rate: f32) {
+        let mut wave = self.device.lock();
+        wave.pattern_phase_inc = rate / wave.freq / 128.0;
+        wave.pattern = Some(*pattern);
+    }
+}
@@ -0,0 +1,182 @@
+//! The save state file format written by [`Chip8::save_state`](crate::cpu::Chip8::save_state).
+//!
+//! A state starts with the 4 byte magic `C8ST` and a little-endian `u16` format version, followed
+//! by chunks. Every chunk is a 4 byte ASCII tag, a little-endian `u32` payload length and the
+//! payload. Multi-byte values are little-endian and flags are single bytes, 0 or 1.
+//!
+//! | Tag    | Payload                                                                          |
+//! |--------|----------------------------------------------------------------------------------|
+//! | `CPU ` | V0-VF, PC (u16), I (u16), delay timer, sound timer, hires, halted, planes, pitch, the 8 RPL flags |
+//! | `STAK` | the number of return addresses (u16) and the addresses (u16 each), bottom first  |
+//! | `MEM ` | the whole address space, its length is the memory size                            |
+//! | `SCRN` | width (u16), height (u16) and one byte per pixel as in [`FrameBuffer`](crate::framebuffer::FrameBuffer) |
+//! | `KEYS` | 16 key flags and the last pressed key, 0xFF if none                               |
+//! | `WAIT` | the register FX0A stores the key in and the key waiting to be released, 0xFF if none |
+//! | `AUDP` | the 16 byte XO-CHIP audio pattern, only present once one has been loaded          |
+//! | `TIME` | machine cycles of the next frame already used in the COSMAC VIP timing model (u32)  |
+//! | `RNG ` | the length of the random source's name, the name and the source's state            |
+//!
+//! Readers skip chunks they do not know, so new chunks can be added without changing the
+//! version. The version is only bumped when the payload of an existing chunk changes, and states
+//! with a newer version than [`VERSION`] are rejected. Quirks, the stack depth and the choice of
+//! random number source are configuration rather than state and are not restored; the state of
+//! the random source is restored if it is the same kind of source.
+//!
+//! Version 1 stored the FX0A wait flag and a `u16` of wait events in `WAIT`, with the FX0A opcode
+//! pushed on the call stack. Versions 1 and 2 stored 64 unused entries at the bottom of `STAK`.
+//! Both are still read; `tests/states` holds a state written by each version.
+
+use crate::error::Chip8Error;
+
+/// Identifies a save state file.
+pub const MAGIC: &[u8; 4] = b"C8ST";
+/// The format version written by this build.
+pub const VERSION: u16 = 3;
+
+fn invalid(reason: &str) -> Chip8Error {
+    Chip8Error::InvalidState { reason: reason.to_string() }
+}
+
+/// Builds a save state chunk by chunk.
+pub(crate) struct StateWriter {
+    bytes: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        StateWriter { bytes }
+    }
+
+    /// Appends the chunk `tag` with the payload written by `write`.
+    pub fn chunk<F: FnOnce(&mut ChunkWriter)>(&mut self, tag: &[u8; 4], write: F) {
+        let mut chunk = ChunkWriter { payload: Vec::new() };
+        write(&mut chunk);
+        self.bytes.extend_from_slice(tag);
+        self.bytes.extend_from_slice(&(chunk.payload.len() as u32).to_le_bytes());
+        self.bytes.extend_from_slice(&chunk.payload);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+pub(crate) struct ChunkWriter {
+    payload: Vec<u8>,
+}
+
+impl ChunkWriter {
+    pub fn u8(&mut self, value: u8) {
+        self.payload.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.payload.push(value as u8);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.payload.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.payload.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.payload.extend_from_slice(bytes);
+    }
+}
+
+/// The chunks of a save state, checked for a valid header and complete chunks.
+pub(crate) struct StateReader<'a> {
+    version: u16,
+    chunks: Vec<([u8; 4], &'a [u8])>,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Chip8Error> {
+        if bytes.len() < 6 || &bytes[..4] != MAGIC {
+            return Err(invalid("not a save state"));
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version > VERSION {
+            return Err(Chip8Error::InvalidState {
+                reason: format!("format version {} is newer than the supported version {}", version, VERSION),
+            });
+        }
+        let mut chunks = Vec::new();
+        let mut rest = &bytes[6..];
+        while !rest.is_empty() {
+            if rest.len() < 8 {
+                return Err(invalid("truncated chunk header"));
+            }
+            let tag = [rest[0], rest[1], rest[2], rest[3]];
+            let len = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+            if rest.len() - 8 < len {
+                return Err(invalid("truncated chunk"));
+            }
+            chunks.push((tag, &rest[8..8 + len]));
+            rest = &rest[8 + len..];
+        }
+        Ok(StateReader { version, chunks })
+    }
+
+    /// The format version the state was written with.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// The chunk `tag`, if the state has one.
+    pub fn chunk(&self, tag: &[u8; 4]) -> Option<ChunkReader<'a>> {
+        self.chunks.iter()
+            .find(|(t, _)| t == tag)
+            .map(|(_, payload)| ChunkReader { payload })
+    }
+
+    /// The chunk `tag`, failing if the state has none.
+    pub fn required(&self, tag: &[u8; 4]) -> Result<ChunkReader<'a>, Chip8Error> {
+        self.chunk(tag).ok_or_else(|| Chip8Error::InvalidState {
+            reason: format!("missing chunk {}", String::from_utf8_lossy(tag)),
+        })
+    }
+}
+
+pub(crate) struct ChunkReader<'a> {
+    payload: &'a [u8],
+}
+
+impl<'a> ChunkReader<'a> {
+    pub fn bytes(&mut self, n: usize) -> Result<&'a [u8], Chip8Error> {
+        if self.payload.len() < n {
+            return Err(invalid("chunk too short"));
+        }
+        let (bytes, rest) = self.payload.split_at(n);
+        self.payload = rest;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Chip8Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, Chip8Error> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> Result<u16, Chip8Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Chip8Error> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Everything left in the chunk.
+    pub fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.payload)
+    }
+}
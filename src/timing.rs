@@ -0,0 +1,108 @@
+//! A model of how long instructions took on the COSMAC VIP.
+//!
+//! The VIP's 1802 CPU ran at 1.7609 MHz with 8 clocks per machine cycle, which gives 3668 machine
+//! cycles per 60 Hz frame. The display DMA and the interrupt routine take about 1084 of them,
+//! leaving [`FRAME_CYCLES`] for the interpreter.
+//!
+//! The costs are counted from the 1802 code of the VIP's CHIP-8 interpreter at 0x000-0x1FF, as
+//! listed in the COSMAC VIP manual (RCA VIP-311). Every 1802 instruction the interpreter executes
+//! takes 2 machine cycles. An instruction pays for the fetch loop at 0x01B, which decodes it and
+//! dispatches through the table at 0x050, and then for its handler: DXYN at 0x070, 00E0 and 00EE
+//! at 0x0E0 and 0x0EE, FXNN at 0x1NN and the others at the addresses in the table. Where a handler
+//! loops or branches, the cost depends on the operands: FX33 loops once per unit of each decimal
+//! digit, a taken skip steps over the next instruction, and DXYN shifts every row once per bit
+//! of misalignment and checks every byte it draws for a collision.
+//!
+//! DXYN prepares the shifted rows, then waits for the display interrupt and draws during the next
+//! frame; [`cycles`] is the preparation and [`draw_cycles`] the drawing. The time FX0A spends in
+//! the monitor's keyboard routine is not counted, since it waits for a key anyway.
+//!
+//! SUPER-CHIP and XO-CHIP instructions never ran on the VIP and are charged a flat cost.
+
+use crate::framebuffer::FrameBuffer;
+use crate::opcode::Opcode;
+
+/// Machine cycles available to the interpreter in one frame.
+pub const FRAME_CYCLES: u32 = 3668 - 1084;
+
+/// The fetch loop at 0x01B up to the handler, and the branch back to it.
+const FETCH_CYCLES: u32 = 68;
+/// The fetch loop of 0NNN, which calls the 1802 routine at NNN instead of dispatching.
+const MACHINE_CODE_FETCH_CYCLES: u32 = 40;
+/// Instructions that are not part of the VIP instruction set.
+const FOREIGN_CYCLES: u32 = 20;
+/// Stepping the program counter over the next instruction when a skip is taken.
+pub const SKIP_CYCLES: u32 = 4;
+
+/// The machine cycles the instruction `opcode` takes, fetch included, or for DXYN the cycles
+/// before it waits for the display interrupt. `registers` and `index` are V0-VF and I before it
+/// ran. `skipped` tells whether a skip instruction skipped the next instruction.
+pub fn cycles(opcode: Opcode, registers: &[u8; 16], index: u16, skipped: bool) -> u32 {
+    let (x, n, nn) = (opcode.x(), opcode.n(), opcode.nn());
+    let vx = registers[x] as u32;
+    let execute = match opcode.0 & 0xF000 {
+        0x0000 => {
+            return MACHINE_CODE_FETCH_CYCLES + match opcode.0 {
+                // clears the 256 bytes of display memory
+                0x00E0 => 6 + 256 * 12,
+                0x00EE => 10,
+                _ => FOREIGN_CYCLES,
+            };
+        }
+        0x1000 => 12,
+        0x2000 => 26,
+        0x3000 | 0x4000 => 10,
+        0x5000 | 0x9000 => 14,
+        0x6000 => 6,
+        0x7000 => 10,
+        // builds and runs a one-instruction 1802 routine
+        0x8000 => if n == 0 { 12 } else { 44 },
+        0xA000 => 12,
+        // adding V0 to the low byte of NNN carries into the high byte
+        0xB000 => 22 + if (opcode.nnn() & 0xFF) + registers[0] as u16 > 0xFF { 2 } else { 0 },
+        0xC000 => 36,
+        0xD000 => 66 + n as u32 * (46 + 20 * (vx & 0x07)),
+        0xE000 => 14,
+        0xF000 => match nn {
+            0x07 | 0x15 | 0x18 => 10,
+            0x0A => 22,
+            0x1E => 16 + if (index & 0xFF) + vx as u16 > 0xFF { 6 } else { 0 },
+            0x29 => 20,
+            // repeated subtraction, once per unit of each decimal digit
+            0x33 => 84 + 16 * (vx / 100 + vx / 10 % 10 + vx % 10),
+            0x55 | 0x65 => 18 + 14 * (x as u32 + 1),
+            _ => FOREIGN_CYCLES,
+        },
+        _ => FOREIGN_CYCLES,
+    };
+    let skip = match opcode.0 & 0xF000 {
+        0x3000 | 0x4000 | 0x5000 | 0x9000 | 0xE000 if skipped => SKIP_CYCLES,
+        _ => 0,
+    };
+    FETCH_CYCLES + execute + skip
+}
+
+/// The machine cycles DXYN spends after the display interrupt drawing the rows `sprite` at
+/// (`vx`, `vy`) onto `frame_buffer`, the screen before the draw. The VIP neither wraps nor draws
+/// rows past the bottom, and a sprite starting in the last byte of a row has no second byte.
+pub fn draw_cycles(vx: u8, vy: u8, sprite: &[u8], frame_buffer: &FrameBuffer) -> u32 {
+    let (column, shift, y) = ((vx as usize & 0x3F) / 8, vx & 0x07, vy as usize & 0x1F);
+    let rows = sprite.len().min(32 - y);
+    // a byte of display memory that the sprite overlaps costs 4 cycles more
+    let collides = |byte_column: usize, y: usize, bits: u8| {
+        (0..8).any(|bit| bits & (0x80 >> bit) != 0 && frame_buffer.pixel(byte_column * 8 + bit, y) & 0x01 != 0)
+    };
+    let mut cycles = 28;
+    for (row, &byte) in sprite[..rows].iter().enumerate() {
+        let bits = (byte as u16) << 8 >> shift;
+        cycles += 34 + if collides(column, y + row, (bits >> 8) as u8) { 4 } else { 0 };
+        if column < 7 {
+            cycles += 16 + if collides(column + 1, y + row, bits as u8) { 4 } else { 0 };
+        }
+    }
+    // the row loop ends at the bottom edge before it checks the row count
+    if y + rows == 32 {
+        cycles -= 4;
+    }
+    cycles
+}
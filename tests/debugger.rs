@@ -0,0 +1,120 @@
+//! Drives the debugger with a scripted command prompt.
+
+use std::cell::RefCell;
+use std::io::{self, Cursor, Write};
+use std::rc::Rc;
+
+use chip8::cpu::Chip8;
+use chip8::debugger::Debugger;
+use chip8::frontend::{Frontend, NoAudio, NoInput, NoVideo};
+
+/// Output shared between the debugger and the test.
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Runs one frame of `program` with the debugger reading `script`, and returns the core and
+/// everything the debugger printed.
+fn debug(program: &[u16], script: &str) -> (Chip8, String) {
+    let rom: Vec<u8> = program.iter().flat_map(|word| word.to_be_bytes()).collect();
+    let mut chip8 = Chip8::new();
+    chip8.init(&rom).unwrap();
+    let output = Output::default();
+    let mut debugger = Debugger::with_io(Box::new(Cursor::new(script.to_string())), Box::new(output.clone()));
+    chip8.run_frame_with(|chip8| debugger.before_step(chip8)).unwrap();
+    let text = String::from_utf8(output.0.borrow().clone()).unwrap();
+    (chip8, text)
+}
+
+// 200: V0 = 5, 202-206: V0 += 1, 208: I = 0, 20A: draw the 0 glyph, 20C: loop
+const PROGRAM: &[u16] = &[0x6005, 0x7001, 0x7001, 0x7001, 0xA000, 0xD015, 0x120C];
+
+#[test]
+fn step_stops_after_n_instructions() {
+    let (_, output) = debug(PROGRAM, "s 2\n");
+    assert!(output.starts_with("0200: 60 05"), "{}", output);
+    assert!(output.contains("0204: 70 01"), "{}", output);
+    assert!(!output.contains("0202:"), "{}", output);
+}
+
+#[test]
+fn address_and_opcode_breakpoints() {
+    let (chip8, output) = debug(PROGRAM, "b 208\nc\nbo DXYN\nbl\nc\n");
+    assert!(output.contains("Breakpoint 0 at 0208"), "{}", output);
+    assert!(output.contains("Breakpoint 0 hit\n0208: A0 00"), "{}", output);
+    assert!(output.contains("1: on DXYN"), "{}", output);
+    assert!(output.contains("Breakpoint 1 hit\n020A: D0 15"), "{}", output);
+    // the debugger ran on to the end of the frame once the input ended
+    assert_eq!(chip8.pc(), 0x20C);
+    assert_eq!(chip8.registers()[0], 8);
+}
+
+#[test]
+fn set_and_write() {
+    let (chip8, output) = debug(PROGRAM, "set V3 2a\nset i 300\nw 300 12 34\nm 300 2\nr\n");
+    assert_eq!(chip8.registers()[3], 0x2A);
+    assert_eq!(&chip8.memory()[0x300..0x302], &[0x12, 0x34]);
+    assert!(output.contains("0300: 12 34\n"), "{}", output);
+    assert!(output.contains("V0=00 V1=00 V2=00 V3=2A"), "{}", output);
+    assert!(output.contains("I=0300"), "{}", output);
+}
+
+#[test]
+fn addresses_out_of_memory() {
+    let (chip8, output) = debug(PROGRAM, "m ffffffffffffffff 10\nw ffffffffffffffff 1\nw fff 1 2\ndis ffffffffffffffff\n");
+    assert!(output.contains("Usage: mem <Addr> [Len]"), "{}", output);
+    assert_eq!(output.matches("Usage: write <Addr> <Byte>...").count(), 2, "{}", output);
+    assert!(output.contains("Address out of memory"), "{}", output);
+    assert_eq!(chip8.memory()[0xFFF], 0);
+}
+
+#[test]
+fn values_that_do_not_fit_are_refused() {
+    let (chip8, output) = debug(PROGRAM, "b 10208
+bl
+set V0 1ff
+set dt 100
+set i 10300
+set pc 10200
+set VG 1
+");
+    // nothing is truncated: no breakpoint at 0208, V0 keeps 5 until the program runs on
+    assert!(output.contains("Usage: break <Addr>"), "{}", output);
+    assert!(!output.contains("Breakpoint 0"), "{}", output);
+    assert_eq!(output.matches("Usage: set <Reg> <Value>").count(), 4, "{}", output);
+    assert!(output.contains("Unknown register"), "{}", output);
+    assert_eq!(chip8.registers()[0], 8);
+    assert_eq!(chip8.pc(), 0x20C);
+}
+
+#[test]
+fn quit_ends_the_session_after_the_frame() {
+    // 200: V0 += 1, 202: jump 200
+    let rom = [0x70, 0x01, 0x12, 0x00];
+    let mut chip8 = Chip8::new();
+    chip8.init(&rom).unwrap();
+    let output = Output::default();
+    let debugger = Debugger::with_io(Box::new(Cursor::new("b 202\nq\n".to_string())), Box::new(output.clone()));
+    let mut frontend = Frontend::new(NoVideo, NoAudio, NoInput);
+    frontend.attach_debugger(debugger);
+    frontend.run_frame(&mut chip8).unwrap();
+    assert!(frontend.quit_requested());
+    // the frame ran to its end without stopping at the breakpoint
+    let text = String::from_utf8(output.0.borrow().clone()).unwrap();
+    assert!(!text.contains("hit"), "{}", text);
+    let counted = chip8.registers()[0];
+    assert_eq!(counted as u32, chip8.instructions_per_frame() / 2);
+    // start() returns without running another frame
+    frontend.start(&mut chip8).unwrap();
+    assert_eq!(chip8.registers()[0], counted);
+}
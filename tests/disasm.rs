@@ -0,0 +1,23 @@
+//! Disassembles ROMs at the limits of the address space.
+
+use chip8::disasm::{self, LineKind};
+use chip8::error::Chip8Error;
+
+#[test]
+fn rom_filling_the_address_space() {
+    // jump to the last instruction, a 4-byte F000 with only its first half in the ROM
+    let mut rom = vec![0; 0x10000 - 0x200];
+    rom[..2].copy_from_slice(&[0x1F, 0xFE]);
+    let end = rom.len();
+    rom[end - 2..].copy_from_slice(&[0xF0, 0x00]);
+    let disassembly = disasm::disassemble(&rom, 0x200).unwrap();
+    let last = disassembly.lines.last().unwrap();
+    assert_eq!(last.address, 0xFFFF);
+    assert_eq!(last.kind, LineKind::Data);
+}
+
+#[test]
+fn rom_beyond_the_address_space() {
+    let rom = vec![0; 70_000];
+    assert_eq!(disasm::disassemble(&rom, 0x200), Err(Chip8Error::RomTooLarge { size: 70_000, max: 0xFE00 }));
+}
@@ -0,0 +1,39 @@
+//! Runs every golden file in tests/golden, see `chip8::golden`, and checks the test ROMs against
+//! their sources.
+//! Rewrite the images after an intended change with `cargo run -- test --bless tests/golden`.
+
+use std::fs;
+use std::path::Path;
+
+use chip8::golden::Golden;
+
+#[test]
+fn golden_images() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut paths: Vec<_> = fs::read_dir(&dir).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "golden"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+    let failures: Vec<String> = paths.iter()
+        .filter_map(|path| Golden::load(path).and_then(|golden| golden.check()).err()
+            .map(|e| format!("{}: {}", path.display(), e)))
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+#[test]
+fn test_roms_match_their_listings() {
+    // every line of a listing that starts with an address has the bytes at that address next to it
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/roms");
+    for name in ["quirks", "schip", "xochip", "wait"] {
+        let listing = fs::read_to_string(dir.join(format!("{}.lst", name))).unwrap();
+        let assembled: Vec<u8> = listing.lines()
+            .map(str::trim_start)
+            .filter(|line| line.split(' ').next().is_some_and(|a| a.len() == 4 && u16::from_str_radix(a, 16).is_ok()))
+            .flat_map(|line| line.split("  ").nth(1).unwrap().split(' ').map(|b| u8::from_str_radix(b, 16).unwrap()).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(assembled, fs::read(dir.join(format!("{}.ch8", name))).unwrap(), "{}", name);
+    }
+}
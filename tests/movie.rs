@@ -0,0 +1,138 @@
+//! Records a headless session into a movie and replays it.
+
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use chip8::cpu::Chip8;
+use chip8::frontend::{Frontend, InputSource, NoAudio, NoInput, NoVideo};
+use chip8::movie::{Movie, MovieHeader, MoviePlayer, MovieRecorder};
+use chip8::quirks::Platform;
+
+const FRAMES: u32 = 600;
+
+/// A movie file shared between the recorder and the test.
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Moves the BRIX paddle left and right, changing direction every 40 frames.
+struct Player {
+    frame: u32,
+}
+
+impl InputSource for Player {
+    fn poll(&mut self, keys: &mut [bool; 16]) {
+        self.frame += 1;
+        let left = (self.frame / 40).is_multiple_of(2);
+        keys[0x4] = left;
+        keys[0x6] = !left;
+    }
+}
+
+fn brix() -> Vec<u8> {
+    fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("roms/BRIX")).unwrap()
+}
+
+/// Records `FRAMES` frames of BRIX, returns the movie and the final state.
+fn record(rom: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut chip8 = Chip8::with_platform(Platform::Vip);
+    chip8.set_seed(7);
+    chip8.init(rom).unwrap();
+    let output = Output::default();
+    let mut frontend = Frontend::new(NoVideo, NoAudio, Player { frame: 0 });
+    frontend.record(MovieRecorder::new(Box::new(output.clone()), &MovieHeader::new(&chip8, rom)).unwrap());
+    for _ in 0..FRAMES {
+        frontend.run_frame(&mut chip8).unwrap();
+    }
+    frontend.stop_recording().unwrap();
+    let movie = output.0.borrow().clone();
+    (movie, chip8.save_state())
+}
+
+#[test]
+fn replay_reaches_the_recorded_state() {
+    let rom = brix();
+    let (movie, recorded) = record(&rom);
+    let movie = Movie::parse(&movie).unwrap();
+    assert_eq!(movie.frames.len(), FRAMES as usize);
+
+    // seeded randomly until the movie sets the seed
+    let mut chip8 = Chip8::with_platform(Platform::Vip);
+    chip8.init(&rom).unwrap();
+    chip8.set_seed(movie.header.seed);
+    movie.header.check(&chip8, &rom).unwrap();
+    let mut frontend = Frontend::new(NoVideo, NoAudio, Player { frame: 1000 });
+    frontend.replay(MoviePlayer::new(movie.frames));
+    for _ in 0..FRAMES {
+        frontend.run_frame(&mut chip8).unwrap();
+    }
+    assert!(chip8.save_state() == recorded, "replay ended in another state");
+
+    // the keys matter: without them the same seed ends elsewhere
+    let mut chip8 = Chip8::with_platform(Platform::Vip);
+    chip8.set_seed(7);
+    chip8.init(&rom).unwrap();
+    let mut frontend = Frontend::new(NoVideo, NoAudio, NoInput);
+    for _ in 0..FRAMES {
+        frontend.run_frame(&mut chip8).unwrap();
+    }
+    assert!(chip8.save_state() != recorded);
+}
+
+#[test]
+fn header_round_trip() {
+    let rom = brix();
+    let (movie, _) = record(&rom);
+    let parsed = Movie::parse(&movie).unwrap();
+    assert_eq!(parsed.to_bytes(), movie);
+    let header = &parsed.header;
+    assert_eq!(header.seed, 7);
+    assert_eq!(header.memory_len, 0x1000);
+    assert_eq!(header.quirks, Platform::Vip.quirks());
+    assert_eq!(header.stack_depth, 12);
+    assert_eq!(header.random_source, "xorshift");
+}
+
+#[test]
+fn replay_with_other_settings_is_rejected() {
+    let rom = brix();
+    let (movie, _) = record(&rom);
+    let header = Movie::parse(&movie).unwrap().header;
+    let check = |configure: &dyn Fn(&mut Chip8), rom: &[u8]| {
+        let mut chip8 = Chip8::with_platform(Platform::Vip);
+        configure(&mut chip8);
+        chip8.init(rom).unwrap();
+        header.check(&chip8, rom).map_err(|e| e.to_string())
+    };
+    assert_eq!(check(&|_| {}, &rom), Ok(()));
+    assert_eq!(check(&|_| {}, &rom[1..]), Err("Invalid movie: recorded with another ROM".to_string()));
+    let error = check(&|chip8| chip8.set_instructions_per_frame(20), &rom).unwrap_err();
+    assert!(error.contains("instructions per frame"), "{}", error);
+    let error = check(&|chip8| chip8.set_quirks(Platform::SuperChip.quirks()), &rom).unwrap_err();
+    assert!(error.contains("quirk shift_uses_vy=on, replaying with off"), "{}", error);
+    let error = check(&|chip8| chip8.set_vip_timing(true), &rom).unwrap_err();
+    assert!(error.contains("--vip-timing off"), "{}", error);
+    let error = check(&|chip8| chip8.set_stack_depth(16), &rom).unwrap_err();
+    assert!(error.contains("stack depth of 12"), "{}", error);
+}
+
+#[test]
+fn old_versions_are_rejected() {
+    let mut movie = b"C8MV".to_vec();
+    movie.extend_from_slice(&1u16.to_le_bytes());
+    movie.extend_from_slice(&7u64.to_le_bytes());
+    assert!(Movie::parse(&movie).is_err());
+}
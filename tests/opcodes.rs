@@ -0,0 +1,570 @@
+//! One or more tests for every instruction, run on a headless core one step at a time.
+
+use chip8::cpu::Chip8;
+use chip8::error::Chip8Error;
+use chip8::quirks::{Platform, Quirks};
+use chip8::random::{RandomSource, VipRandom};
+
+/// A core with `program` loaded at 0x200.
+fn load_with(mut chip8: Chip8, program: &[u16]) -> Chip8 {
+    let rom: Vec<u8> = program.iter().flat_map(|word| word.to_be_bytes()).collect();
+    chip8.set_seed(0);
+    chip8.init(&rom).unwrap();
+    chip8
+}
+
+fn load(program: &[u16]) -> Chip8 {
+    load_with(Chip8::new(), program)
+}
+
+fn step(chip8: &mut Chip8, n: usize) {
+    for _ in 0..n {
+        chip8.step().unwrap();
+    }
+}
+
+/// Loads `program` and executes one instruction per word.
+fn run(program: &[u16]) -> Chip8 {
+    let mut chip8 = load(program);
+    step(&mut chip8, program.len());
+    chip8
+}
+
+fn v(chip8: &Chip8, x: usize) -> u8 {
+    chip8.registers()[x]
+}
+
+#[test]
+fn clear_screen() {
+    // draw the 0 glyph, then clear
+    let chip8 = run(&[0xA000, 0xD005, 0x00E0]);
+    assert!(chip8.frame_buffer().pixels().iter().all(|&p| p == 0));
+}
+
+#[test]
+fn jump() {
+    let chip8 = run(&[0x1300]);
+    assert_eq!(chip8.pc(), 0x300);
+}
+
+#[test]
+fn call_and_return() {
+    // 200: call 206, 202: (return lands here), 206: return
+    let mut chip8 = load(&[0x2206, 0x0000, 0x0000, 0x00EE]);
+    step(&mut chip8, 1);
+    assert_eq!(chip8.pc(), 0x206);
+    assert_eq!(chip8.stack().last(), Some(&0x202));
+    step(&mut chip8, 1);
+    assert_eq!(chip8.pc(), 0x202);
+}
+
+#[test]
+fn nested_calls_fill_the_stack() {
+    let mut chip8 = load(&[0x2202, 0x2204, 0x2206, 0x00EE]);
+    step(&mut chip8, 3);
+    assert_eq!(chip8.stack(), &[0x202, 0x204, 0x206]);
+    step(&mut chip8, 1);
+    assert_eq!(chip8.stack(), &[0x202, 0x204]);
+    assert_eq!(chip8.pc(), 0x206);
+}
+
+#[test]
+fn runaway_recursion_overflows_the_stack() {
+    let mut chip8 = load(&[0x2200]);
+    step(&mut chip8, 16);
+    assert_eq!(chip8.stack().len(), 16);
+    assert_eq!(chip8.step(), Err(Chip8Error::StackOverflow { pc: 0x200, opcode: 0x2200 }));
+
+    let mut chip8 = load_with(Chip8::with_platform(Platform::Vip), &[0x2200]);
+    assert_eq!(chip8.stack_depth(), 12);
+    step(&mut chip8, 12);
+    assert_eq!(chip8.step(), Err(Chip8Error::StackOverflow { pc: 0x200, opcode: 0x2200 }));
+}
+
+#[test]
+fn return_without_call_underflows_the_stack() {
+    let mut chip8 = load(&[0x00EE]);
+    assert_eq!(chip8.step(), Err(Chip8Error::StackUnderflow { pc: 0x200, opcode: 0x00EE }));
+}
+
+#[test]
+fn skip_if_equal_immediate() {
+    assert_eq!(run(&[0x6012, 0x3012]).pc(), 0x206);
+    assert_eq!(run(&[0x6012, 0x3013]).pc(), 0x204);
+}
+
+#[test]
+fn skip_if_not_equal_immediate() {
+    assert_eq!(run(&[0x6012, 0x4013]).pc(), 0x206);
+    assert_eq!(run(&[0x6012, 0x4012]).pc(), 0x204);
+}
+
+#[test]
+fn skip_if_registers_equal() {
+    assert_eq!(run(&[0x6012, 0x6112, 0x5010]).pc(), 0x208);
+    assert_eq!(run(&[0x6012, 0x6113, 0x5010]).pc(), 0x206);
+}
+
+#[test]
+fn skip_if_registers_not_equal() {
+    assert_eq!(run(&[0x6012, 0x6113, 0x9010]).pc(), 0x208);
+    assert_eq!(run(&[0x6012, 0x6112, 0x9010]).pc(), 0x206);
+}
+
+#[test]
+fn skip_passes_over_long_load() {
+    // the skipped F000 NNNN is 4 bytes long
+    let mut chip8 = load(&[0x3000, 0xF000, 0x1234]);
+    step(&mut chip8, 1);
+    assert_eq!(chip8.pc(), 0x206);
+}
+
+#[test]
+fn load_immediate() {
+    assert_eq!(v(&run(&[0x6A42]), 0xA), 0x42);
+}
+
+#[test]
+fn add_immediate_wraps_without_carry() {
+    let chip8 = run(&[0x60FF, 0x6F05, 0x7002]);
+    assert_eq!(v(&chip8, 0), 0x01);
+    assert_eq!(v(&chip8, 0xF), 0x05);
+}
+
+#[test]
+fn register_copy_and_logic() {
+    let chip8 = run(&[0x600C, 0x610A, 0x8210, 0x8311, 0x8400, 0x8412, 0x8500, 0x8513]);
+    assert_eq!(v(&chip8, 2), 0x0A);
+    assert_eq!(v(&chip8, 3), 0x0A);
+    assert_eq!(v(&chip8, 4), 0x08);
+    assert_eq!(v(&chip8, 5), 0x06);
+}
+
+#[test]
+fn logic_resets_vf_with_quirk() {
+    let quirks = Quirks { vf_reset: true, ..Quirks::default() };
+    for opcode in [0x8011, 0x8012, 0x8013] {
+        let mut chip8 = load_with(Chip8::with_quirks(quirks), &[0x6F07, opcode]);
+        step(&mut chip8, 2);
+        assert_eq!(v(&chip8, 0xF), 0, "{:04X}", opcode);
+    }
+    assert_eq!(v(&run(&[0x6F07, 0x8011]), 0xF), 0x07);
+}
+
+#[test]
+fn add_sets_carry() {
+    let chip8 = run(&[0x60F0, 0x6120, 0x8014]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 0xF)), (0x10, 1));
+    let chip8 = run(&[0x6010, 0x6120, 0x8014]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 0xF)), (0x30, 0));
+}
+
+#[test]
+fn subtract_sets_vf_when_there_is_no_borrow() {
+    let chip8 = run(&[0x6030, 0x6110, 0x8015]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 0xF)), (0x20, 1));
+    let chip8 = run(&[0x6010, 0x6130, 0x8015]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 0xF)), (0xE0, 0));
+    let chip8 = run(&[0x6010, 0x6110, 0x8015]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 0xF)), (0x00, 1));
+}
+
+#[test]
+fn reverse_subtract_sets_vf_when_there_is_no_borrow() {
+    let chip8 = run(&[0x6010, 0x6130, 0x8017]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 0xF)), (0x20, 1));
+    let chip8 = run(&[0x6030, 0x6110, 0x8017]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 0xF)), (0xE0, 0));
+}
+
+#[test]
+fn shift_right_sets_vf_to_the_lost_bit() {
+    let chip8 = run(&[0x6005, 0x8006]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 0xF)), (0x02, 1));
+    let chip8 = run(&[0x6004, 0x8006]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 0xF)), (0x02, 0));
+}
+
+#[test]
+fn shift_left_sets_vf_to_the_lost_bit() {
+    let chip8 = run(&[0x6081, 0x800E]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 0xF)), (0x02, 1));
+    let chip8 = run(&[0x6041, 0x800E]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 0xF)), (0x82, 0));
+}
+
+#[test]
+fn shift_uses_vy_with_quirk() {
+    let quirks = Quirks { shift_uses_vy: true, ..Quirks::default() };
+    let mut chip8 = load_with(Chip8::with_quirks(quirks), &[0x6001, 0x6106, 0x8016]);
+    step(&mut chip8, 3);
+    assert_eq!((v(&chip8, 0), v(&chip8, 1)), (0x03, 0x06));
+    let chip8 = run(&[0x6001, 0x6106, 0x8016]);
+    assert_eq!(v(&chip8, 0), 0x00);
+}
+
+#[test]
+fn flag_wins_when_vf_is_the_destination() {
+    assert_eq!(v(&run(&[0x6FF0, 0x6120, 0x8F14]), 0xF), 1);
+    assert_eq!(v(&run(&[0x6F10, 0x6120, 0x8F15]), 0xF), 0);
+    assert_eq!(v(&run(&[0x6F03, 0x8F06]), 0xF), 1);
+    assert_eq!(v(&run(&[0x6F40, 0x8F0E]), 0xF), 0);
+}
+
+#[test]
+fn set_index() {
+    assert_eq!(run(&[0xA123]).index(), 0x123);
+}
+
+#[test]
+fn jump_with_offset() {
+    assert_eq!(run(&[0x6004, 0x6208, 0xB300]).pc(), 0x304);
+    let quirks = Quirks { jump_uses_vx: true, ..Quirks::default() };
+    let mut chip8 = load_with(Chip8::with_quirks(quirks), &[0x6004, 0x6308, 0xB300]);
+    step(&mut chip8, 3);
+    assert_eq!(chip8.pc(), 0x308);
+}
+
+#[test]
+fn random_is_masked_and_reproducible() {
+    let program: Vec<u16> = (0..16).map(|x| 0xC00F | x << 8).collect();
+    let first = run(&program);
+    assert!(first.registers().iter().all(|&r| r <= 0x0F));
+    assert_eq!(first.registers(), run(&program).registers());
+}
+
+#[test]
+fn vip_random_sequence() {
+    // R9 becomes 00D9, 00 plus the interpreter byte at 0x1D9 (0x19) is 0x19, 0x19 + 0x0C = 0x25
+    let mut random = VipRandom::new(0xD8);
+    let bytes: Vec<u8> = (0..6).map(|_| random.next_byte()).collect();
+    assert_eq!(bytes, [0x25, 0x05, 0x0C, 0xEE, 0x82, 0xA8]);
+
+    // the VIP's interrupt routine increments R9 once per frame
+    let mut chip8 = load(&[0xC0FF, 0xC1FF, 0xC27F]);
+    chip8.set_random_source(Box::new(VipRandom::new(0)));
+    chip8.set_seed(0xD8);
+    for _ in 0..3 {
+        step(&mut chip8, 1);
+        chip8.tick_timers();
+    }
+    assert_eq!(&chip8.registers()[..3], &[0x25, 0x3C, 0x77 & 0x7F]);
+}
+
+#[test]
+fn draw_sets_vf_on_collision() {
+    // draw the 0 glyph twice at (1, 2)
+    let mut chip8 = load(&[0x6001, 0x6102, 0xA000, 0xD015, 0xD015]);
+    step(&mut chip8, 4);
+    assert_eq!(v(&chip8, 0xF), 0);
+    let screen = chip8.frame_buffer();
+    // the top row of 0 is 0xF0
+    assert_eq!((1..5).map(|x| screen.pixel(x, 2)).collect::<Vec<_>>(), vec![1, 1, 1, 1]);
+    assert_eq!(screen.pixel(5, 2), 0);
+    step(&mut chip8, 1);
+    assert_eq!(v(&chip8, 0xF), 1);
+    assert!(chip8.frame_buffer().pixels().iter().all(|&p| p == 0));
+}
+
+#[test]
+fn draw_wraps_the_origin_and_the_sprite() {
+    // the origin (66, 33) wraps to (2, 1) and the row wraps around the right edge from x = 62
+    let mut chip8 = load(&[0x6042, 0x6121, 0xA000, 0xD011, 0x603E, 0xD011]);
+    step(&mut chip8, 6);
+    let screen = chip8.frame_buffer();
+    assert_eq!(screen.pixel(2, 1), 1);
+    assert_eq!((screen.pixel(62, 1), screen.pixel(63, 1), screen.pixel(0, 1), screen.pixel(1, 1)), (1, 1, 1, 1));
+}
+
+#[test]
+fn draw_clips_with_quirk() {
+    let quirks = Quirks { clip_sprites: true, ..Quirks::default() };
+    let mut chip8 = load_with(Chip8::with_quirks(quirks), &[0x603E, 0x6101, 0xA000, 0xD011]);
+    step(&mut chip8, 4);
+    let screen = chip8.frame_buffer();
+    assert_eq!((screen.pixel(62, 1), screen.pixel(63, 1)), (1, 1));
+    assert_eq!((screen.pixel(0, 1), screen.pixel(1, 1)), (0, 0));
+}
+
+#[test]
+fn skip_on_key() {
+    let mut chip8 = load(&[0x6005, 0xE09E]);
+    chip8.set_key(5, true);
+    step(&mut chip8, 2);
+    assert_eq!(chip8.pc(), 0x206);
+    let mut chip8 = load(&[0x6005, 0xE0A1]);
+    chip8.set_key(5, true);
+    step(&mut chip8, 2);
+    assert_eq!(chip8.pc(), 0x204);
+    assert_eq!(run(&[0x6005, 0xE09E]).pc(), 0x204);
+    assert_eq!(run(&[0x6005, 0xE0A1]).pc(), 0x206);
+}
+
+#[test]
+fn wait_for_key_completes_on_release() {
+    let mut chip8 = load(&[0xF30A, 0x6001]);
+    step(&mut chip8, 3);
+    assert!(chip8.waiting_for_key());
+    assert_eq!(chip8.pc(), 0x202);
+    chip8.set_key(0xB, true);
+    step(&mut chip8, 2);
+    assert!(chip8.waiting_for_key());
+    assert_eq!(v(&chip8, 3), 0);
+    chip8.set_key(0xB, false);
+    step(&mut chip8, 1);
+    assert!(!chip8.waiting_for_key());
+    assert_eq!(v(&chip8, 3), 0xB);
+    assert!(chip8.stack().is_empty());
+    step(&mut chip8, 1);
+    assert_eq!(v(&chip8, 0), 1);
+}
+
+#[test]
+fn wait_for_key_completes_on_press_without_quirk() {
+    let quirks = Quirks { key_wait_release: false, ..Quirks::default() };
+    let mut chip8 = load_with(Chip8::with_quirks(quirks), &[0xF30A]);
+    step(&mut chip8, 2);
+    chip8.set_key(0xB, true);
+    step(&mut chip8, 1);
+    assert!(!chip8.waiting_for_key());
+    assert_eq!(v(&chip8, 3), 0xB);
+}
+
+#[test]
+fn wait_for_key_ignores_earlier_presses() {
+    let mut chip8 = load(&[0xF30A]);
+    chip8.set_key(0x4, true);
+    chip8.set_key(0x4, false);
+    step(&mut chip8, 3);
+    assert!(chip8.waiting_for_key());
+}
+
+#[test]
+fn wait_for_key_accepts_a_held_key() {
+    let mut chip8 = load(&[0xF30A]);
+    chip8.set_key(0x4, true);
+    step(&mut chip8, 2);
+    chip8.set_key(0x4, false);
+    step(&mut chip8, 1);
+    assert_eq!(v(&chip8, 3), 0x4);
+}
+
+#[test]
+fn timers_run_while_waiting_for_key() {
+    let mut chip8 = load(&[0x6005, 0xF015, 0xF018, 0xF30A]);
+    for _ in 0..3 {
+        chip8.run_frame().unwrap();
+    }
+    assert!(chip8.waiting_for_key());
+    assert_eq!((chip8.delay_timer(), chip8.sound_timer()), (2, 2));
+}
+
+#[test]
+fn wait_for_key_survives_save_states() {
+    let mut chip8 = load(&[0xF30A]);
+    step(&mut chip8, 1);
+    chip8.set_key(0xB, true);
+    step(&mut chip8, 1);
+    let state = chip8.save_state();
+
+    let mut restored = Chip8::new();
+    restored.load_state(&state).unwrap();
+    assert!(restored.waiting_for_key());
+    restored.set_key(0xB, false);
+    step(&mut restored, 1);
+    assert!(!restored.waiting_for_key());
+    assert_eq!(v(&restored, 3), 0xB);
+}
+
+#[test]
+fn timers() {
+    let mut chip8 = run(&[0x6003, 0xF015, 0x6105, 0xF118]);
+    assert_eq!((chip8.delay_timer(), chip8.sound_timer()), (3, 5));
+    assert!(chip8.sound_active());
+    chip8.tick_timers();
+    assert_eq!((chip8.delay_timer(), chip8.sound_timer()), (2, 4));
+    for _ in 0..10 {
+        chip8.tick_timers();
+    }
+    assert_eq!((chip8.delay_timer(), chip8.sound_timer()), (0, 0));
+    assert!(!chip8.sound_active());
+}
+
+#[test]
+fn sound_timer_runs_without_delay_timer() {
+    let mut chip8 = run(&[0x6002, 0xF018]);
+    chip8.tick_timers();
+    chip8.tick_timers();
+    assert_eq!((chip8.delay_timer(), chip8.sound_timer()), (0, 0));
+}
+
+#[test]
+fn read_delay_timer() {
+    let mut chip8 = load(&[0x6009, 0xF015, 0xF207]);
+    step(&mut chip8, 2);
+    chip8.tick_timers();
+    step(&mut chip8, 1);
+    assert_eq!(v(&chip8, 2), 8);
+}
+
+#[test]
+fn run_frame_ticks_timers_once() {
+    let mut chip8 = load(&[0x6009, 0xF015, 0x1204]);
+    chip8.run_frame().unwrap();
+    assert_eq!(chip8.delay_timer(), 8);
+}
+
+#[test]
+fn add_to_index() {
+    assert_eq!(run(&[0xA123, 0x6010, 0xF01E]).index(), 0x133);
+    assert_eq!(run(&[0xAFFF, 0x6002, 0xF01E]).index(), 0x1001);
+}
+
+#[test]
+fn font_digit() {
+    assert_eq!(run(&[0x600A, 0xF029]).index(), 10 * 5);
+    // only the low nibble selects the digit
+    assert_eq!(run(&[0x601A, 0xF029]).index(), 10 * 5);
+    let chip8 = run(&[0x6000, 0xF029]);
+    assert_eq!(&chip8.memory()[0..5], &[0xF0, 0x90, 0x90, 0x90, 0xF0]);
+}
+
+#[test]
+fn binary_coded_decimal() {
+    let chip8 = run(&[0x60FE, 0xA300, 0xF033]);
+    assert_eq!(&chip8.memory()[0x300..0x303], &[2, 5, 4]);
+}
+
+#[test]
+fn store_and_load_registers() {
+    let chip8 = run(&[0x6011, 0x6122, 0x6233, 0xA300, 0xF255]);
+    assert_eq!(&chip8.memory()[0x300..0x304], &[0x11, 0x22, 0x33, 0x00]);
+    assert_eq!(chip8.index(), 0x303);
+
+    let mut chip8 = load(&[0xA300, 0xF165]);
+    chip8.memory_mut()[0x300..0x303].copy_from_slice(&[7, 8, 9]);
+    step(&mut chip8, 2);
+    assert_eq!((v(&chip8, 0), v(&chip8, 1), v(&chip8, 2)), (7, 8, 0));
+    assert_eq!(chip8.index(), 0x302);
+}
+
+#[test]
+fn store_leaves_index_without_quirk() {
+    let quirks = Quirks { load_store_increments_i: false, ..Quirks::default() };
+    let mut chip8 = load_with(Chip8::with_quirks(quirks), &[0xA300, 0xF255, 0xF265]);
+    step(&mut chip8, 3);
+    assert_eq!(chip8.index(), 0x300);
+}
+
+#[test]
+fn memory_access_out_of_bounds_faults() {
+    let mut chip8 = load(&[0xAFFF, 0xF155]);
+    step(&mut chip8, 1);
+    assert_eq!(
+        chip8.step(),
+        Err(Chip8Error::MemoryOutOfBounds { pc: 0x202, opcode: 0xF155, address: 0x1000 })
+    );
+    let mut chip8 = load(&[0x1FFF]);
+    step(&mut chip8, 1);
+    assert_eq!(chip8.step(), Err(Chip8Error::MemoryOutOfBounds { pc: 0xFFF, opcode: 0, address: 0x1000 }));
+}
+
+#[test]
+fn unknown_opcode_faults() {
+    for opcode in [0x0123, 0x5121, 0x8128, 0xE1FF, 0xF1FF] {
+        let mut chip8 = load(&[opcode]);
+        assert_eq!(chip8.step(), Err(Chip8Error::UnknownOpcode { pc: 0x200, opcode }), "{:04X}", opcode);
+    }
+}
+
+#[test]
+fn schip_resolution_and_exit() {
+    let mut chip8 = load_with(Chip8::with_platform(Platform::SuperChip), &[0x00FF, 0x00FE, 0x00FF, 0x00FD, 0x6001]);
+    step(&mut chip8, 1);
+    assert_eq!((chip8.frame_buffer().width(), chip8.frame_buffer().height()), (128, 64));
+    step(&mut chip8, 1);
+    assert_eq!((chip8.frame_buffer().width(), chip8.frame_buffer().height()), (64, 32));
+    step(&mut chip8, 3);
+    assert!(chip8.halted());
+    assert_eq!(v(&chip8, 0), 0);
+}
+
+#[test]
+fn schip_scrolling() {
+    // draw the top row of 0 at (0, 0), then scroll down 2 and right 4
+    let mut chip8 = load(&[0xA000, 0xD001, 0x00C2, 0x00FB]);
+    step(&mut chip8, 4);
+    let screen = chip8.frame_buffer();
+    assert_eq!(screen.pixel(0, 0), 0);
+    assert_eq!((screen.pixel(4, 2), screen.pixel(7, 2), screen.pixel(8, 2)), (1, 1, 0));
+
+    // draw it at (8, 3), then scroll left 4 and up 1
+    let mut chip8 = load(&[0x6008, 0x6103, 0xA000, 0xD011, 0x00FC, 0x00D1]);
+    step(&mut chip8, 5);
+    let screen = chip8.frame_buffer();
+    assert_eq!((screen.pixel(3, 3), screen.pixel(4, 3), screen.pixel(7, 3), screen.pixel(8, 3)), (0, 1, 1, 0));
+    step(&mut chip8, 1);
+    let screen = chip8.frame_buffer();
+    assert_eq!(screen.pixel(4, 3), 0);
+    assert_eq!((screen.pixel(3, 2), screen.pixel(4, 2), screen.pixel(7, 2), screen.pixel(8, 2)), (0, 1, 1, 0));
+}
+
+#[test]
+fn schip_big_font_and_flags() {
+    let chip8 = run(&[0x6001, 0xF030]);
+    assert_eq!(chip8.index(), 0x50 + 10);
+    let chip8 = run(&[0x6011, 0x6122, 0xF175, 0x6000, 0x6100, 0xF185]);
+    assert_eq!((v(&chip8, 0), v(&chip8, 1)), (0x11, 0x22));
+}
+
+#[test]
+fn schip_large_sprite() {
+    let mut chip8 = load(&[0x00FF, 0xA300, 0xD000]);
+    chip8.memory_mut()[0x300..0x320].fill(0xFF);
+    step(&mut chip8, 3);
+    let lit = chip8.frame_buffer().pixels().iter().filter(|&&p| p == 1).count();
+    assert_eq!(lit, 16 * 16);
+}
+
+#[test]
+fn xochip_register_ranges() {
+    // a descending range stores V2 first
+    let chip8 = run(&[0x6011, 0x6122, 0x6233, 0xA300, 0x5202]);
+    assert_eq!(&chip8.memory()[0x300..0x303], &[0x33, 0x22, 0x11]);
+    assert_eq!(chip8.index(), 0x300);
+    let mut chip8 = load(&[0xA300, 0x5133]);
+    chip8.memory_mut()[0x300..0x303].copy_from_slice(&[7, 8, 9]);
+    step(&mut chip8, 2);
+    assert_eq!((v(&chip8, 1), v(&chip8, 2), v(&chip8, 3)), (7, 8, 9));
+}
+
+#[test]
+fn xochip_long_index() {
+    let mut chip8 = load(&[0xF000, 0xBEEF]);
+    step(&mut chip8, 1);
+    assert_eq!(chip8.index(), 0xBEEF);
+    assert_eq!(chip8.pc(), 0x204);
+}
+
+#[test]
+fn xochip_planes() {
+    // select plane 2 and draw the top row of 0
+    let mut chip8 = load_with(Chip8::with_platform(Platform::XoChip), &[0xF201, 0xA000, 0xD001]);
+    step(&mut chip8, 3);
+    assert_eq!(chip8.frame_buffer().pixel(0, 0), 2);
+    // both planes take consecutive sprite data
+    let mut chip8 = load_with(Chip8::with_platform(Platform::XoChip), &[0xF301, 0xA000, 0xD001]);
+    step(&mut chip8, 3);
+    // the rows are 0xF0 for plane 1 and 0x90 for plane 2
+    assert_eq!((chip8.frame_buffer().pixel(0, 0), chip8.frame_buffer().pixel(1, 0)), (3, 1));
+}
+
+#[test]
+fn xochip_audio() {
+    let mut chip8 = load(&[0xA300, 0xF002, 0x6070, 0xF03A]);
+    chip8.memory_mut()[0x300..0x310].copy_from_slice(&[0xAA; 16]);
+    assert_eq!(chip8.audio_pattern(), None);
+    step(&mut chip8, 4);
+    assert_eq!(chip8.audio_pattern(), Some(&[0xAA; 16]));
+    assert!(chip8.audio_pattern_rate() > 4000.0);
+}
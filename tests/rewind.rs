@@ -0,0 +1,81 @@
+//! Pushes states into the rewind history and steps back through them.
+
+use chip8::cpu::Chip8;
+use chip8::rewind::Rewind;
+
+fn step_back(rewind: &mut Rewind) -> Option<Vec<u8>> {
+    rewind.step_back().map(<[u8]>::to_vec)
+}
+
+#[test]
+fn push_and_step_back() {
+    let mut chip8 = Chip8::new();
+    chip8.init(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+    let mut rewind = Rewind::new(10);
+    let mut states = Vec::new();
+    for _ in 0..5 {
+        states.push(chip8.save_state());
+        rewind.push(chip8.save_state());
+        chip8.step().unwrap();
+    }
+    assert_eq!(rewind.len(), 5);
+    for state in states.iter().rev().skip(1) {
+        assert_eq!(step_back(&mut rewind).as_ref(), Some(state));
+    }
+    // the oldest state stays
+    assert_eq!(step_back(&mut rewind), None);
+    assert_eq!(rewind.len(), 1);
+}
+
+#[test]
+fn states_of_different_length() {
+    let mut chip8 = Chip8::new();
+    // 200: hires, 202: lores, 204: load an audio pattern
+    chip8.init(&[0x00, 0xFF, 0x00, 0xFE, 0xF0, 0x02]).unwrap();
+    let mut rewind = Rewind::new(10);
+    let mut states = vec![chip8.save_state()];
+    for _ in 0..3 {
+        chip8.step().unwrap();
+        states.push(chip8.save_state());
+    }
+    // the screen grows and shrinks again, then the AUDP chunk appears
+    assert!(states[1].len() > states[0].len());
+    assert!(states[2].len() < states[1].len());
+    assert!(states[3].len() > states[2].len());
+    for state in states.iter() {
+        rewind.push(state.clone());
+    }
+    for state in states.iter().rev().skip(1) {
+        assert_eq!(step_back(&mut rewind).as_ref(), Some(state));
+    }
+}
+
+#[test]
+fn zero_runs_longer_than_u16_max() {
+    // the changes are further apart than one run length can count
+    let long = u16::MAX as usize * 2 + 10;
+    let mut older = vec![0; long];
+    older[0] = 1;
+    older[long - 1] = 2;
+    let mut newer = older.clone();
+    newer[0] = 3;
+    newer[u16::MAX as usize + 1] = 4;
+    let mut rewind = Rewind::new(10);
+    rewind.push(vec![0; long]);
+    rewind.push(older.clone());
+    rewind.push(newer);
+    assert_eq!(step_back(&mut rewind), Some(older));
+    assert_eq!(step_back(&mut rewind), Some(vec![0; long]));
+}
+
+#[test]
+fn oldest_state_is_dropped_at_capacity() {
+    let mut rewind = Rewind::new(3);
+    for frame in 0..5u8 {
+        rewind.push(vec![frame; 8]);
+        assert_eq!(rewind.len(), (frame as usize + 1).min(3));
+    }
+    assert_eq!(step_back(&mut rewind), Some(vec![3; 8]));
+    assert_eq!(step_back(&mut rewind), Some(vec![2; 8]));
+    assert_eq!(step_back(&mut rewind), None);
+}
@@ -0,0 +1,52 @@
+//! Loads save states written by every format version and checks they resume the same machine.
+//!
+//! `tests/states/v<N>.state` was written by the build that introduced version N, after 3 frames of
+//! `tests/roms/wait.ch8`: a subroutine sets V1 to 42, draws the 0 glyph at (2, 2) and waits for a
+//! key with F30A before it returns to a loop at 202.
+
+use std::fs;
+use std::path::Path;
+
+use chip8::cpu::Chip8;
+
+fn load(version: u16) -> Chip8 {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("tests/states/v{}.state", version));
+    let mut chip8 = Chip8::new();
+    chip8.load_state(&fs::read(path).unwrap()).unwrap();
+    chip8
+}
+
+#[test]
+fn old_versions_resume_like_the_current_one() {
+    let current = load(3);
+    assert_eq!(current.stack(), &[0x202]);
+    assert!(current.waiting_for_key());
+    assert_eq!(current.registers()[1], 0x42);
+    for version in [1, 2] {
+        let chip8 = load(version);
+        // the unused entries at the bottom of the stack are gone, and in version 1 the FX0A on top
+        assert_eq!(chip8.stack(), current.stack(), "version {}", version);
+        assert!(chip8.waiting_for_key(), "version {}", version);
+        assert_eq!(chip8.pc(), current.pc(), "version {}", version);
+        assert_eq!(chip8.registers(), current.registers(), "version {}", version);
+        assert_eq!(chip8.memory(), current.memory(), "version {}", version);
+        assert_eq!(chip8.frame_buffer().pixels(), current.frame_buffer().pixels(), "version {}", version);
+    }
+}
+
+#[test]
+fn old_versions_complete_the_wait() {
+    for version in [1, 2, 3] {
+        let mut chip8 = load(version);
+        chip8.set_key(0x5, true);
+        chip8.run_frame().unwrap();
+        chip8.set_key(0x5, false);
+        chip8.run_frame().unwrap();
+        assert!(!chip8.waiting_for_key(), "version {}", version);
+        assert_eq!(chip8.registers()[3], 0x5, "version {}", version);
+        // F30A stored into V3 rather than the register of an opcode left on the stack, and the
+        // subroutine returned to the loop
+        assert!(chip8.stack().is_empty(), "version {}", version);
+        assert_eq!(chip8.pc(), 0x202, "version {}", version);
+    }
+}
@@ -0,0 +1,140 @@
+//! The COSMAC VIP cycle model and how `run_frame` spends a frame's cycles with `--vip-timing`.
+
+use chip8::cpu::Chip8;
+use chip8::opcode::Opcode;
+use chip8::framebuffer::FrameBuffer;
+use chip8::timing::{self, FRAME_CYCLES, SKIP_CYCLES};
+
+fn load(program: &[u16]) -> Chip8 {
+    let rom: Vec<u8> = program.iter().flat_map(|word| word.to_be_bytes()).collect();
+    let mut chip8 = Chip8::new();
+    chip8.init(&rom).unwrap();
+    chip8.set_vip_timing(true);
+    chip8
+}
+
+/// The number of instructions of a loop costing `costs` that start within the first `cycles`.
+fn started_within(costs: &[u32], cycles: u32) -> u32 {
+    let (mut time, mut started) = (0, 0);
+    for cost in costs.iter().cycle() {
+        if time >= cycles {
+            return started;
+        }
+        time += cost;
+        started += 1;
+    }
+    unreachable!()
+}
+
+#[test]
+fn taken_skips_cost_more() {
+    let registers = [0; 16];
+    for opcode in [0x3000, 0x4001, 0x5010, 0x9010, 0xE09E, 0xE0A1] {
+        let not_taken = timing::cycles(Opcode(opcode), &registers, 0, false);
+        assert_eq!(timing::cycles(Opcode(opcode), &registers, 0, true), not_taken + SKIP_CYCLES, "{:04X}", opcode);
+    }
+    // only skips pay for skipping
+    assert_eq!(timing::cycles(Opcode(0x6000), &registers, 0, true), timing::cycles(Opcode(0x6000), &registers, 0, false));
+}
+
+#[test]
+fn instruction_costs() {
+    let mut registers = [0; 16];
+    let cycles = |opcode: u16, registers: &[u8; 16], index: u16| timing::cycles(Opcode(opcode), registers, index, false);
+    // counted from the interpreter's code, fetch included
+    assert_eq!(cycles(0x6012, &registers, 0), 74);
+    assert_eq!(cycles(0x1200, &registers, 0), 80);
+    assert_eq!(cycles(0x00EE, &registers, 0), 50);
+    assert_eq!(cycles(0x00E0, &registers, 0), 3118);
+    assert_eq!(cycles(0x8014, &registers, 0), 112);
+    assert_eq!(cycles(0xF155, &registers, 0), 114);
+    assert_eq!(cycles(0xFF65, &registers, 0), 310);
+    assert_eq!(cycles(0xF033, &registers, 0), 152);
+    registers[0] = 255;
+    assert_eq!(cycles(0xF033, &registers, 0), 152 + 16 * (2 + 5 + 5));
+    // carrying into the high byte of I takes longer
+    assert_eq!(cycles(0xF01E, &registers, 0x200), 84);
+    assert_eq!(cycles(0xF01E, &registers, 0x201), 90);
+    // drawing at an X that is not a multiple of 8 shifts every row once per bit
+    registers[0] = 0;
+    assert_eq!(cycles(0xD015, &registers, 0), 134 + 5 * 46);
+    registers[0] = 3;
+    assert_eq!(cycles(0xD015, &registers, 0), 134 + 5 * (46 + 3 * 20));
+    // DXY0 draws no rows on the VIP
+    assert_eq!(cycles(0xD010, &registers, 0), 134);
+}
+
+#[test]
+fn draw_costs() {
+    let glyph = [0xF0, 0x90, 0x90, 0x90, 0xF0];
+    let mut frame_buffer = FrameBuffer::new(64, 32);
+    assert_eq!(timing::draw_cycles(0, 0, &glyph, &frame_buffer), 28 + 5 * 50);
+    // a sprite in the last byte of a row has no second byte
+    assert_eq!(timing::draw_cycles(60, 0, &glyph, &frame_buffer), 28 + 5 * 34);
+    // rows below the bottom edge are not drawn
+    assert_eq!(timing::draw_cycles(0, 27, &glyph, &frame_buffer), 28 + 5 * 50 - 4);
+    assert_eq!(timing::draw_cycles(0, 29, &glyph, &frame_buffer), 28 + 3 * 50 - 4);
+    // every byte that hits a set pixel costs more
+    frame_buffer.flip(0, 0, 1);
+    frame_buffer.flip(0, 4, 1);
+    assert_eq!(timing::draw_cycles(0, 0, &glyph, &frame_buffer), 28 + 5 * 50 + 2 * 4);
+    assert_eq!(timing::draw_cycles(1, 0, &glyph, &frame_buffer), 28 + 5 * 50);
+}
+
+#[test]
+fn frame_budget_and_carry_over() {
+    // 200: V0 += 1, 202: jump 200
+    let mut chip8 = load(&[0x7001, 0x1200]);
+    let registers = [0; 16];
+    let costs = [timing::cycles(Opcode(0x7001), &registers, 0, false), timing::cycles(Opcode(0x1200), &registers, 0, false)];
+    for frame in 1..=3 {
+        chip8.run_frame().unwrap();
+        // an instruction that runs past the end of a frame delays the next frame's first one
+        let expected = started_within(&costs, frame * FRAME_CYCLES);
+        assert_eq!(chip8.registers()[0] as u32, expected.div_ceil(2), "frame {}", frame);
+    }
+    assert!(chip8.registers()[0] > 0);
+}
+
+#[test]
+fn instructions_per_frame_is_ignored() {
+    let mut chip8 = load(&[0x7001, 0x1200]);
+    chip8.set_instructions_per_frame(1);
+    chip8.run_frame().unwrap();
+    assert!(chip8.registers()[0] > 1);
+}
+
+#[test]
+fn draw_ends_the_frame() {
+    // 200: I = 0, 202: draw the 0 glyph, 204: V1 += 1, 206: jump 204
+    let mut chip8 = load(&[0xA000, 0xD005, 0x7101, 0x1204]);
+    chip8.run_frame().unwrap();
+    assert_eq!(chip8.pc(), 0x204);
+    assert_eq!(chip8.registers()[1], 0);
+
+    // the sprite is drawn during the next frame, which loses the cycles of drawing
+    let registers = [0; 16];
+    let draw = timing::draw_cycles(0, 0, &[0xF0, 0x90, 0x90, 0x90, 0xF0], &FrameBuffer::new(64, 32));
+    let costs = [timing::cycles(Opcode(0x7101), &registers, 0, false), timing::cycles(Opcode(0x1204), &registers, 0, false)];
+    chip8.run_frame().unwrap();
+    let expected = started_within(&costs, FRAME_CYCLES - draw);
+    assert_eq!(chip8.registers()[1] as u32, expected.div_ceil(2));
+}
+
+#[test]
+fn key_wait_ends_with_a_full_frame() {
+    // 200: wait for a key, 202: V1 += 1, 204: jump 202
+    let mut chip8 = load(&[0xF00A, 0x7101, 0x1202]);
+    chip8.run_frame().unwrap();
+    chip8.set_key(5, true);
+    chip8.run_frame().unwrap();
+    chip8.set_key(5, false);
+    chip8.run_frame().unwrap();
+    assert_eq!(chip8.registers()[0], 5);
+
+    // the frame the wait ends in is not charged for the instruction after it
+    let registers = [0; 16];
+    let costs = [timing::cycles(Opcode(0x7101), &registers, 0, false), timing::cycles(Opcode(0x1202), &registers, 0, false)];
+    let expected = started_within(&costs, FRAME_CYCLES);
+    assert_eq!(chip8.registers()[1] as u32, expected.div_ceil(2));
+}